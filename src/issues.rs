@@ -0,0 +1,303 @@
+use crate::structs::{IssueLookupRules, ValidationError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// The status of an issue, or `None` when the issue does not exist.
+pub type IssueStatus = Option<String>;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct CacheEntry {
+    status: IssueStatus,
+    checked_at: i64,
+}
+
+/// Looks up issues in a local export of the issues (JSON or CSV),
+/// or in a Jira compatible REST endpoint through curl, whose responses are cached.
+pub struct IssueTracker<'a> {
+    rules: &'a IssueLookupRules,
+    index: Option<HashMap<String, IssueStatus>>,
+    cache: HashMap<String, CacheEntry>,
+    cache_changed: bool,
+    /// The failed lookups are not retried for the other commits of the push.
+    failures: HashMap<String, String>,
+    /// The issues that do not exist are not cached across pushes, since they may be created right after a rejected push.
+    /// They are not looked up again for the other commits of the push though.
+    missing: HashSet<String>,
+}
+
+impl<'a> IssueTracker<'a> {
+    pub fn new(rules: &'a IssueLookupRules) -> Result<Self, ValidationError> {
+        if rules.index_file.is_none() && rules.url.is_none() {
+            return Err(ValidationError::IssueLookupConfig(
+                "Either \"index_file\" or \"url\" is required".to_owned(),
+            ));
+        }
+
+        let index = match &rules.index_file {
+            Some(index_file) => Some(read_index_file(index_file).map_err(|e| {
+                ValidationError::IssueLookupConfig(format!(
+                    "The index file \"{}\" failed to be read: {}",
+                    index_file, e
+                ))
+            })?),
+            None => None,
+        };
+
+        // A missing or broken cache is rebuilt, instead of failing the lookups.
+        let cache = rules
+            .cache_file
+            .as_ref()
+            .and_then(|cache_file| std::fs::read_to_string(cache_file).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Ok(Self {
+            rules,
+            index,
+            cache,
+            cache_changed: false,
+            failures: HashMap::new(),
+            missing: HashSet::new(),
+        })
+    }
+
+    /// Returns the status of an issue. The endpoint is used for the issues that are missing from the index file.
+    /// An error is returned when the endpoint fails, so that the caller can fail open or closed.
+    pub fn lookup(&mut self, key: &str) -> Result<IssueStatus, String> {
+        if let Some(status) = self.index.as_ref().and_then(|index| index.get(key)) {
+            return Ok(status.clone());
+        }
+        let url = match &self.rules.url {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        if self.missing.contains(key) {
+            return Ok(None);
+        }
+        if let Some(entry) = self.cache.get(key) {
+            // The missing issues of older caches are looked up again.
+            if entry.status.is_some()
+                && now - entry.checked_at < self.rules.cache_ttl_seconds.unwrap_or(3600) as i64
+            {
+                return Ok(entry.status.clone());
+            }
+        }
+
+        if let Some(failure) = self.failures.get(key) {
+            return Err(failure.clone());
+        }
+        let status = match self.fetch(&url.replace("{key}", key)) {
+            Ok(v) => v,
+            Err(e) => {
+                self.failures.insert(key.to_owned(), e.clone());
+                return Err(e);
+            }
+        };
+        if status.is_none() {
+            self.missing.insert(key.to_owned());
+            return Ok(None);
+        }
+        self.cache.insert(
+            key.to_owned(),
+            CacheEntry {
+                status: status.clone(),
+                checked_at: now,
+            },
+        );
+        self.cache_changed = true;
+        Ok(status)
+    }
+
+    /// Fetches an issue with curl. The token is passed through stdin, so that it is not visible in the process list.
+    fn fetch(&self, url: &str) -> Result<IssueStatus, String> {
+        let mut child = Command::new("curl")
+            .arg("--silent")
+            .arg("--show-error")
+            .arg("--max-time")
+            .arg(self.rules.timeout_seconds.unwrap_or(5).to_string())
+            .arg("--header")
+            .arg("Accept: application/json")
+            .arg("--header")
+            .arg("@-")
+            .arg("--write-out")
+            .arg("\n%{http_code}")
+            .arg(url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("curl failed to start: {}", e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let token = self
+                .rules
+                .token_variable
+                .as_ref()
+                .and_then(|variable| std::env::var(variable).ok());
+            if let Some(token) = token {
+                writeln!(stdin, "Authorization: Bearer {}", token)
+                    .map_err(|e| format!("curl failed to get the token: {}", e))?;
+            }
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("curl failed: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "curl failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let output = String::from_utf8_lossy(&output.stdout);
+        let (body, http_code) = output.rsplit_once('\n').unwrap_or(("", &output));
+        parse_issue_response(body, http_code.trim())
+    }
+}
+
+impl Drop for IssueTracker<'_> {
+    fn drop(&mut self) {
+        if let (Some(cache_file), true) = (&self.rules.cache_file, self.cache_changed) {
+            if let Ok(contents) = serde_json::to_string(&self.cache) {
+                let _ = std::fs::write(cache_file, contents);
+            }
+        }
+    }
+}
+
+/// Parses the response of a Jira compatible issue endpoint, e.g. "/rest/api/2/issue/{key}?fields=status".
+fn parse_issue_response(body: &str, http_code: &str) -> Result<IssueStatus, String> {
+    match http_code {
+        "200" => {
+            let issue: Value = serde_json::from_str(body)
+                .map_err(|e| format!("The response is not valid JSON: {}", e))?;
+            match get_issue_status(&issue) {
+                Some(status) => Ok(Some(status)),
+                None => Err("The response has no issue status".to_owned()),
+            }
+        }
+        "404" => Ok(None),
+        _ => Err(format!("The endpoint responded with HTTP {}", http_code)),
+    }
+}
+
+/// Returns the status of a Jira issue ("fields.status.name"), or of a plain issue ("status").
+fn get_issue_status(issue: &Value) -> Option<String> {
+    issue
+        .pointer("/fields/status/name")
+        .or_else(|| issue.get("status"))
+        .and_then(Value::as_str)
+        .map(|status| status.to_owned())
+}
+
+/// Reads an export of the issues. The issues without a status are considered open.
+/// JSON files have a list of issues, or a Jira search result with an "issues" list,
+/// where each issue has a "key" and a "status" (or "fields.status.name").
+/// CSV files have a header line with a "Key" (or "Issue key") column, and an optional "Status" column.
+fn read_index_file(path: &str) -> Result<HashMap<String, IssueStatus>, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    if path.to_lowercase().ends_with(".csv") {
+        return parse_csv_index(&contents);
+    }
+
+    let index: Value = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+    let issues = match index.get("issues").unwrap_or(&index).as_array() {
+        Some(v) => v,
+        None => return Err("The JSON index is not a list of issues".to_owned()),
+    };
+    Ok(issues
+        .iter()
+        .filter_map(|issue| {
+            let key = issue.get("key")?.as_str()?.to_owned();
+            Some((key, Some(get_issue_status(issue).unwrap_or_default())))
+        })
+        .collect())
+}
+
+fn parse_csv_index(contents: &str) -> Result<HashMap<String, IssueStatus>, String> {
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let header = match lines.next() {
+        Some(v) => split_csv_line(v),
+        None => return Ok(HashMap::new()),
+    };
+    let column = |names: &[&str]| {
+        header
+            .iter()
+            .position(|column| names.iter().any(|name| column.eq_ignore_ascii_case(name)))
+    };
+    let key_column = match column(&["key", "issue key"]) {
+        Some(v) => v,
+        None => return Err("The CSV index has no \"Key\" column".to_owned()),
+    };
+    let status_column = column(&["status"]);
+
+    Ok(lines
+        .map(split_csv_line)
+        .filter_map(|fields| {
+            let key = fields.get(key_column)?.clone();
+            let status = status_column
+                .and_then(|status_column| fields.get(status_column).cloned())
+                .unwrap_or_default();
+            Some((key, Some(status)))
+        })
+        .collect())
+}
+
+/// Splits a CSV line into its fields. Quoted fields may have commas and doubled quotes.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field).trim().to_owned()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_owned());
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_issue_response() {
+        let body = r#"{ "key": "FOO-1", "fields": { "status": { "name": "In Progress" } } }"#;
+        assert_eq!(
+            parse_issue_response(body, "200"),
+            Ok(Some("In Progress".to_owned()))
+        );
+        assert_eq!(parse_issue_response("", "404"), Ok(None));
+        assert!(parse_issue_response("{}", "200").is_err());
+        assert!(parse_issue_response("", "503").is_err());
+    }
+
+    #[test]
+    fn test_parse_csv_index() {
+        let contents =
+            "Summary,Issue key,Status\n\"Login, \"\"SSO\"\"\",FOO-1,Open\nLogout,FOO-2,Done\n";
+        let index = parse_csv_index(contents).unwrap();
+        assert_eq!(index.len(), 2);
+        assert_eq!(index["FOO-1"], Some("Open".to_owned()));
+        assert_eq!(index["FOO-2"], Some("Done".to_owned()));
+        assert_eq!(
+            split_csv_line("\"Login, \"\"SSO\"\"\",FOO-1"),
+            vec!["Login, \"SSO\"".to_owned(), "FOO-1".to_owned()]
+        );
+        assert!(parse_csv_index("Summary\nLogin").is_err());
+    }
+}
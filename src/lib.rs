@@ -1,4 +1,9 @@
+#![allow(non_snake_case)]
+
+pub mod git;
+pub mod issues;
 pub mod loggers;
+pub mod secrets;
 pub mod structs;
 pub mod traits;
 pub mod validators;
@@ -31,14 +36,35 @@ pub fn get_hook(path: &str) -> Hook {
 }
 
 pub fn get_stdin_data() -> String {
+    get_raw_stdin_data().replace('\n', "")
+}
+
+/// Returns the stdin input as is, for hooks that get multiple lines (e.g. pre-receive).
+pub fn get_raw_stdin_data() -> String {
     let mut stdin_input = String::new();
     let stdin = std::io::stdin();
     let mut stdin_handle = stdin.lock();
     stdin_handle.read_to_string(&mut stdin_input).unwrap();
-    stdin_input = stdin_input.replace('\n', "");
     stdin_input
 }
 
+/// Reads the commit message file that git passes to the commit-msg hook.
+pub fn get_commit_message<P: AsRef<Path>>(path: P) -> std::io::Result<String> {
+    let message = std::fs::read_to_string(path)?;
+    Ok(strip_commit_message_comments(&message))
+}
+
+/// Removes the comment lines that git adds to the commit message file,
+/// and everything below the scissors line of `git commit --verbose`.
+pub fn strip_commit_message_comments(message: &str) -> String {
+    message
+        .lines()
+        .take_while(|line| *line != "# ------------------------ >8 ------------------------")
+        .filter(|line| !line.starts_with('#'))
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
 pub fn get_repo_path(input: &str) -> String {
     // Remove everything after .git, if exists and return the first part.
     let parts: Vec<&str> = input.split(".git").collect();
@@ -49,6 +75,23 @@ pub fn get_repo_path(input: &str) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_strip_commit_message_comments() {
+        let message = "FOO-1: The title
+
+The body
+# Please enter the commit message for your changes. Lines starting
+# with '#' will be ignored, and an empty message aborts the commit.
+# ------------------------ >8 ------------------------
+diff --git a/file b/file
++added line
+";
+        assert_eq!(
+            strip_commit_message_comments(message),
+            "FOO-1: The title\n\nThe body"
+        );
+    }
+
     #[test]
     fn test_get_path() {
         let path_str = "/home/user/repo.git";
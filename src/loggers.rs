@@ -1,3 +1,4 @@
+use crate::structs::{Severity, ValidationReport};
 use std::io::Write;
 
 pub fn create_logging_directory() -> std::io::Result<()> {
@@ -9,13 +10,36 @@ pub fn create_logging_directory() -> std::io::Result<()> {
 }
 
 pub fn get_logging_directory() -> String {
-    return format!("./{}", clap::crate_name!());
+    format!("./{}", clap::crate_name!())
 }
 
 pub fn log_to_ui(error: &str) {
     println!("GL-HOOK-ERR: Error: {}", error);
 }
 
+/// Warnings are printed to stderr, which git forwards to the user too.
+/// The hook scripts reject the push only when something is printed to stdout.
+pub fn log_warning_to_ui(warning: &str) {
+    eprintln!("Warning: {}", warning);
+    let _ = log_to_file(&format!("Warning: {}", warning));
+}
+
+/// Prints every violation of the report, so that the push is rejected with all of them at once.
+pub fn log_report_to_ui(report: &ValidationReport) {
+    for violation in &report.violations {
+        match violation.severity {
+            Severity::Error => log_to_ui(&violation.to_string()),
+            Severity::Warning => log_warning_to_ui(&violation.to_string()),
+            Severity::Off => {}
+        }
+    }
+}
+
+/// Used by the client side hooks, where the GitLab specific prefix is not needed.
+pub fn log_to_console(error: &str) {
+    println!("Error: {}", error);
+}
+
 // This function is used for logging.
 pub fn log_to_file(message: &str) -> std::io::Result<()> {
     let dt = chrono::Utc::now();
@@ -31,6 +55,7 @@ pub fn log_to_file(message: &str) -> std::io::Result<()> {
             .read(true)
             .write(true)
             .create(true)
+            .truncate(true)
             .open(logging_directory_file)?
     };
     file.write_all(format!("{}\n", message).as_ref())?;
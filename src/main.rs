@@ -6,14 +6,12 @@ use std::process::exit;
 use GitPolicyEnforcer::*;
 
 fn main() {
-    // Create the logging directory.
-    let _ = create_logging_directory();
-
     // Arguments stuff
     let regex_argument = "regex";
     let regex_argument_value = "regex-value";
     let hooks_argument = "hook";
     let rules_argument = "rules";
+    let message_file_argument = "message-file";
     let matches = clap::Command::new(clap::crate_name!())
         .version(clap::crate_version!())
         .author(clap::crate_authors!("\n"))
@@ -44,23 +42,29 @@ fn main() {
                 .help("The first ($0) argument of the executing hook script")
                 .takes_value(true),
         )
+        .arg(
+            clap::Arg::new(message_file_argument)
+                .long(message_file_argument)
+                .help("The commit message file ($1) that git passes to the commit-msg hook")
+                .takes_value(true),
+        )
         .get_matches();
 
     // Functionality of on the fly validation.
     let regex_str = matches.value_of(regex_argument);
     if let Some(regex_str) = regex_str {
-        let value_to_test = vec![matches.value_of(regex_argument_value).unwrap().to_owned()];
+        let value_to_test = matches.value_of(regex_argument_value).unwrap();
         let regex = create_regex(regex_str);
         if let Err(e) = regex {
             println!("\nValidation failed at regex compilation: {}\n", e);
             exit(0);
         }
         let regex = regex.unwrap();
-        let validation_result = validate_title_format(&value_to_test, &regex);
+        let validation_result = validate_title_format(value_to_test, &regex);
         if validation_result.is_err() {
             println!(
                 "\nValidation failed: Value \"{}\" failed to validate against regex \"{}\"\n",
-                &value_to_test[0], &regex
+                value_to_test, &regex
             );
         } else {
             println!("Validation succeeded");
@@ -72,13 +76,22 @@ fn main() {
     // Start executing the actual program.
     let hooks_argument_value = matches.value_of(hooks_argument).unwrap_or("");
     let hook = get_hook(hooks_argument_value);
+
+    // The commit message file path is relative to the working tree,
+    // so it has to be read before changing to the git directory.
+    let commit_message = matches
+        .value_of(message_file_argument)
+        .map(get_commit_message);
+
     let path = get_repo_path(hooks_argument_value);
     let git_repo_directory = std::path::Path::new(&path);
-    match std::env::set_current_dir(&git_repo_directory) {
-        Ok(_) => {}
-        Err(_) => {
-            let _ = log_to_file("set_current_dir failed");
-        }
+    let set_current_dir_result = std::env::set_current_dir(git_repo_directory);
+
+    // Create the logging directory, inside the git directory.
+    // For the client side hooks this keeps it out of the working tree.
+    let _ = create_logging_directory();
+    if set_current_dir_result.is_err() {
+        let _ = log_to_file("set_current_dir failed");
     }
 
     // Start executing based on the hook.
@@ -89,23 +102,62 @@ fn main() {
         }
         Hook::Update => {
             let update_hook_data = UpdateHookData::get_data(get_stdin_data().as_str());
-            let rules = match matches.value_of("rules") {
-                Some(value) => match parse_rules(value) {
-                    Ok(v) => v,
-                    Err(e) => {
-                        let _ = log_to_file(&format!("!parse_rules: {}", e));
-                        exit(1)
-                    }
-                },
+            let rules = get_rules(&matches, rules_argument);
+            match validate_update_rules(&rules, &update_hook_data) {
+                Ok(report) => log_report_to_ui(&report),
+                Err(e) => log_to_ui(&e.to_string()),
+            }
+        }
+        Hook::PreReceive => {
+            let pre_receive_hook_data = PreReceiveHookData::get_data(get_raw_stdin_data().as_str());
+            let rules = get_rules(&matches, rules_argument);
+            match validate_pre_receive_rules(&rules, &pre_receive_hook_data) {
+                Ok(report) => log_report_to_ui(&report),
+                Err(e) => log_to_ui(&e.to_string()),
+            }
+        }
+        Hook::CommitMsg => {
+            let commit_message = match commit_message {
+                Some(Ok(v)) => v,
+                Some(Err(e)) => {
+                    let _ = log_to_file(&format!("!get_commit_message: {}", e));
+                    exit(1);
+                }
                 None => {
-                    let _ = log_to_file("No rules argument was provided");
+                    let _ = log_to_file("No message-file argument was provided");
                     exit(1);
                 }
             };
-            match validate_update_rules(&rules.update, &update_hook_data) {
-                Ok(()) => {}
-                Err(e) => log_to_ui(&e.to_string()),
+            let rules = get_rules(&matches, rules_argument);
+            let branch = git::get_current_branch();
+            match validate_commit_msg_rules(&rules, &branch, &commit_message) {
+                Ok(errors) => {
+                    for (error, severity) in errors {
+                        match severity {
+                            Severity::Error => log_to_console(&error.to_string()),
+                            Severity::Warning => log_warning_to_ui(&error.to_string()),
+                            Severity::Off => {}
+                        }
+                    }
+                }
+                Err(e) => log_to_console(&e.to_string()),
+            }
+        }
+    }
+}
+
+fn get_rules(matches: &clap::ArgMatches, rules_argument: &str) -> Rules {
+    match matches.value_of(rules_argument) {
+        Some(value) => match parse_rules(value) {
+            Ok(v) => v,
+            Err(e) => {
+                let _ = log_to_file(&format!("!parse_rules: {}", e));
+                exit(1)
             }
+        },
+        None => {
+            let _ = log_to_file("No rules argument was provided");
+            exit(1);
         }
     }
 }
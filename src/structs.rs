@@ -1,8 +1,9 @@
 use crate::traits::HookData;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
 
 #[derive(Deserialize, Debug)]
-pub enum Hook { Update, Invalid }
+pub enum Hook { Update, PreReceive, CommitMsg, Invalid }
 
 impl From<&str> for Hook {
     fn from(path: &str) -> Self {
@@ -10,6 +11,8 @@ impl From<&str> for Hook {
         if parts.is_empty() { return Self::Invalid; }
         match *parts.last().unwrap() {
             "update" => Self::Update,
+            "pre-receive" => Self::PreReceive,
+            "commit-msg" => Self::CommitMsg,
             _ => Self::Invalid,
         }
     }
@@ -17,98 +20,960 @@ impl From<&str> for Hook {
 
 #[derive(Debug, PartialEq)]
 pub enum ValidationError {
-    TitleFormat(String),
-    TitleMaxLength(u8),
+    TitleFormat { pattern: String, title: String },
+    TitleMaxLength { max_length: u8, length: usize, title: String },
     BodyRequired,
-    BodyMaxLineLength(u8),
-    EnforceSquashMerge,
+    BodyMaxLineLength { max_length: u8, length: usize, line: String, line_number: usize },
+    EnforceSquashMerge { commits: usize },
+    SquashMergeCommit { parents: usize },
+    MergeCommitNotAllowed { parents: usize },
+    TooManyParents { parents: usize, max_parents: u8 },
+    MergeCommitRequired,
+    RefNameFormat { ref_type: String, ref_name: String, pattern: String, description: Option<String> },
+    NonFastForward { old_commit: String, new_commit: String },
+    RefDeletion { ref_name: String },
+    IdentityEmailDomain { role: String, email: String, allowed_domains: Vec<String> },
+    IdentityEmailFormat { role: String, email: String, pattern: String },
+    IdentityLocal { role: String, email: String },
+    IdentityNameFormat { role: String, name: String, pattern: String },
+    IdentityFullName { role: String, name: String },
+    CommitterNotPusher { pusher: String, email: String, pushed_by_allowed: bool },
+    UnknownPusher { variable: String },
+    UnsignedCommit,
+    SignedOffByMissing { author: String },
+    ConventionalFormat { title: String },
+    ConventionalType { commit_type: String, types: Vec<String> },
+    ConventionalScopeMissing,
+    ConventionalScope { scope: String, scopes: Vec<String> },
+    ConventionalBreakingFooter,
+    ConventionalBreakingMarker,
+    TicketIdMissing { location: String },
+    TicketIdMismatch { branch: String, branch_ids: Vec<String>, message_ids: Vec<String> },
+    FileTooLarge { path: String, size: u64, max_size: u64 },
+    ForbiddenPath { path: String, pattern: String },
+    LfsPointerMissing { path: String, pattern: String },
+    LfsPointerInvalid { path: String, reason: String },
+    LfsObjectMissing { path: String, oid: String },
+    SecretDetected { path: String, line_number: usize, detector: String, masked_secret: String, fingerprint: String },
+    IssueUnknown { key: String },
+    IssueClosed { key: String, status: String },
+    IssueLookupFailed { key: String, reason: String },
+    TrailerMissing { key: String },
+    TrailerForbidden { key: String },
+    TrailerFormat { key: String, value: String, pattern: String },
+    TrailerNameEmail { key: String, value: String },
+    SignedOffByMismatch { author: String, sign_offs: Vec<String> },
+    UnverifiedSignature,
+    SignerMismatch { signer: String, committer_email: String },
     RegexCompilation(String),
+    UserEmailsFile { path: String, error: String },
+    IssueLookupConfig(String),
+    SecretScanningConfig(String),
 }
 
 impl std::fmt::Display for ValidationError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let common_message_part = "Validation failed: ";
         match self {
-            Self::TitleFormat(pattern) => write!(
+            Self::TitleFormat { pattern, title } => write!(
                 f,
-                "{}The format of the commit title is wrong. Please follow the following regex pattern: {}",
-                common_message_part, pattern
+                "{}The format of the commit title \"{}\" is wrong. Please follow the following regex pattern: {}",
+                common_message_part, title, pattern
             ),
-            Self::BodyMaxLineLength(body_max_line_length) => write!(
+            Self::BodyMaxLineLength { max_length, length, line, line_number } => write!(
                 f,
-                "{}The body line length is larger than the allowed {} characters",
-                common_message_part, body_max_line_length
+                "{}The length of the body line {} (\"{}\") is {} characters, larger than the allowed {} characters",
+                common_message_part, line_number, line, length, max_length
             ),
-            Self::TitleMaxLength(title_max_length) => write!(
+            Self::TitleMaxLength { max_length, length, title } => write!(
                 f,
-                "{}The title length is larger than the allowed {} characters",
-                common_message_part, title_max_length
+                "{}The length of the title \"{}\" is {} characters, larger than the allowed {} characters",
+                common_message_part, title, length, max_length
             ),
-            Self::EnforceSquashMerge => write!(
+            Self::EnforceSquashMerge { commits } => write!(
                 f,
-                "{}Make sure to squash before trying to merge.",
-                common_message_part
+                "{}The push brings {} new commits, while only one is allowed. Make sure to squash before trying to merge.",
+                common_message_part, commits
+            ),
+            Self::SquashMergeCommit { parents } => write!(
+                f,
+                "{}The commit is a merge commit with {} parents. Make sure to squash before trying to merge.",
+                common_message_part, parents
             ),
             Self::BodyRequired => write!(
                 f,
                 "{}Body missing from commit(s). Ensure that all commits contain a commit body.",
                 common_message_part
             ),
+            Self::RefNameFormat { ref_type, ref_name, pattern, description } => {
+                write!(f, "{}The {} name \"{}\" is not allowed.", common_message_part, ref_type, ref_name)?;
+                if let Some(description) = description {
+                    write!(f, " {}.", description.trim_end_matches('.'))?;
+                }
+                write!(f, " It must match the regex pattern: {}", pattern)
+            }
+            Self::NonFastForward { old_commit, new_commit } => write!(
+                f,
+                "{}Rewriting the history of the branch is not allowed. The new commit {} does not descend from the old commit {}. Make sure to pull and rebase before pushing, instead of force pushing.",
+                common_message_part, new_commit, old_commit
+            ),
+            Self::RefDeletion { ref_name } => match ref_name.strip_prefix("refs/tags/") {
+                Some(tag) => write!(f, "{}Deleting the tag \"{}\" is not allowed.", common_message_part, tag),
+                None => write!(f, "{}Deleting the branch \"{}\" is not allowed.", common_message_part, ref_name),
+            },
+            Self::IdentityEmailDomain { role, email, allowed_domains } => write!(
+                f,
+                "{}The {} email \"{}\" is not allowed. The email domain must be one of: {}",
+                common_message_part, role, email, allowed_domains.join(", ")
+            ),
+            Self::IdentityEmailFormat { role, email, pattern } => write!(
+                f,
+                "{}The {} email \"{}\" is not allowed. It must match the regex pattern: {}",
+                common_message_part, role, email, pattern
+            ),
+            Self::IdentityLocal { role, email } => write!(
+                f,
+                "{}The {} email \"{}\" is a local machine identity. Configure your identity with git config user.name and user.email",
+                common_message_part, role, email
+            ),
+            Self::IdentityNameFormat { role, name, pattern } => write!(
+                f,
+                "{}The {} name \"{}\" is not allowed. It must match the regex pattern: {}",
+                common_message_part, role, name, pattern
+            ),
+            Self::IdentityFullName { role, name } => write!(
+                f,
+                "{}The {} name \"{}\" is not a full name. Use your first and last name, e.g. \"John Doe\"",
+                common_message_part, role, name
+            ),
+            Self::CommitterNotPusher { pusher, email, pushed_by_allowed } => {
+                write!(f, "{}The committer email \"{}\" does not belong to the pusher \"{}\".", common_message_part, email, pusher)?;
+                if *pushed_by_allowed {
+                    write!(f, " Add a \"Pushed-by: {}\" trailer, if you push the commit of someone else", pusher)?;
+                }
+                Ok(())
+            }
+            Self::UnknownPusher { variable } => write!(
+                f,
+                "{}The pusher is unknown, because the \"{}\" environment variable is not set",
+                common_message_part, variable
+            ),
+            Self::MergeCommitNotAllowed { parents } => write!(
+                f,
+                "{}The commit is a merge commit with {} parents, but the branch must have a linear history. Rebase instead of merging",
+                common_message_part, parents
+            ),
+            Self::TooManyParents { parents, max_parents } => write!(
+                f,
+                "{}The commit has {} parents, which is more than the maximum of {}",
+                common_message_part, parents, max_parents
+            ),
+            Self::MergeCommitRequired => write!(
+                f,
+                "{}The commit is not a merge commit, but the branch accepts only merge commits. Merge with git merge --no-ff",
+                common_message_part
+            ),
+            Self::UnsignedCommit => write!(f, "{}The commit is not signed. Sign it with git commit -S", common_message_part),
+            Self::UnverifiedSignature => write!(
+                f,
+                "{}The commit signature could not be verified. The signing key is unknown or the signature is invalid",
+                common_message_part
+            ),
+            Self::SignerMismatch { signer, committer_email } => write!(
+                f,
+                "{}The commit is signed by \"{}\", whose email is not the committer email \"{}\"",
+                common_message_part, signer, committer_email
+            ),
+            Self::SignedOffByMissing { author } => write!(
+                f,
+                "{}The commit has no \"Signed-off-by\" trailer. Sign it off with git commit -s, as \"{}\"",
+                common_message_part, author
+            ),
+            Self::SignedOffByMismatch { author, sign_offs } => write!(
+                f,
+                "{}The commit is not signed off by its author \"{}\", but by: {}",
+                common_message_part, author, sign_offs.join(", ")
+            ),
+            Self::ConventionalFormat { title } => write!(
+                f,
+                "{}The title \"{}\" is not a Conventional Commit title, like \"<type>[(<scope>)][!]: <description>\"",
+                common_message_part, title
+            ),
+            Self::ConventionalType { commit_type, types } => write!(
+                f,
+                "{}The type \"{}\" is not allowed. The type must be one of: {}",
+                common_message_part, commit_type, types.join(", ")
+            ),
+            Self::ConventionalScopeMissing => write!(f, "{}The scope is required, like \"<type>(<scope>): <description>\"", common_message_part),
+            Self::ConventionalScope { scope, scopes } => write!(
+                f,
+                "{}The scope \"{}\" is not allowed. The scope must be one of: {}",
+                common_message_part, scope, scopes.join(", ")
+            ),
+            Self::ConventionalBreakingFooter => write!(
+                f,
+                "{}The \"!\" breaking change marker requires a \"BREAKING CHANGE: <description>\" footer",
+                common_message_part
+            ),
+            Self::ConventionalBreakingMarker => write!(
+                f,
+                "{}The \"BREAKING CHANGE\" footer requires a \"!\" breaking change marker in the title, like \"<type>!: <description>\"",
+                common_message_part
+            ),
+            Self::TicketIdMissing { location } => write!(f, "{}The {} has no ticket ID", common_message_part, location),
+            Self::TicketIdMismatch { branch, branch_ids, message_ids } => write!(
+                f,
+                "{}The ticket IDs of the commit message ({}) do not match the ticket IDs of the branch \"{}\" ({})",
+                common_message_part, message_ids.join(", "), branch, branch_ids.join(", ")
+            ),
+            Self::FileTooLarge { path, size, max_size } => write!(
+                f,
+                "{}The file \"{}\" is {}, which is more than the maximum file size of {}",
+                common_message_part, path, format_size(*size), format_size(*max_size)
+            ),
+            Self::ForbiddenPath { path, pattern } => write!(
+                f,
+                "{}The file \"{}\" is not allowed in the repository, because it matches the forbidden path \"{}\"",
+                common_message_part, path, pattern
+            ),
+            Self::LfsPointerMissing { path, pattern } => write!(
+                f,
+                "{}The file \"{}\" must be stored with Git LFS, because it matches \"{}\". Track it with \"git lfs track\", and commit it again",
+                common_message_part, path, pattern
+            ),
+            Self::LfsPointerInvalid { path, reason } => write!(
+                f,
+                "{}The file \"{}\" is not a valid Git LFS pointer: {}",
+                common_message_part, path, reason
+            ),
+            Self::LfsObjectMissing { path, oid } => write!(
+                f,
+                "{}The Git LFS object {} of the file \"{}\" is missing on the server. Push it with \"git lfs push\"",
+                common_message_part, oid, path
+            ),
+            Self::SecretDetected { path, line_number, detector, masked_secret, fingerprint } => write!(
+                f,
+                "{}A possible secret ({}) was found in \"{}\" at line {}: {}. Remove it from the commit and revoke it. If it is not a secret, allowlist the fingerprint \"{}\"",
+                common_message_part, detector, path, line_number, masked_secret, fingerprint
+            ),
+            Self::IssueUnknown { key } => write!(f, "{}The ticket \"{}\" does not exist", common_message_part, key),
+            Self::IssueClosed { key, status } => write!(
+                f,
+                "{}The ticket \"{}\" is closed (status: {}). Reference an open ticket",
+                common_message_part, key, status
+            ),
+            Self::IssueLookupFailed { key, reason } => write!(
+                f,
+                "{}The ticket \"{}\" could not be looked up: {}",
+                common_message_part, key, reason
+            ),
+            Self::TrailerMissing { key } => write!(f, "{}The commit has no \"{}\" trailer", common_message_part, key),
+            Self::TrailerForbidden { key } => write!(f, "{}The \"{}\" trailer is not allowed", common_message_part, key),
+            Self::TrailerFormat { key, value, pattern } => write!(
+                f,
+                "{}The \"{}\" trailer value \"{}\" does not match the regex pattern: {}",
+                common_message_part, key, value, pattern
+            ),
+            Self::TrailerNameEmail { key, value } => write!(
+                f,
+                "{}The \"{}\" trailer value \"{}\" is not like \"Name <email>\"",
+                common_message_part, key, value
+            ),
             Self::RegexCompilation(regex) => write!(
                 f,
                 "Regex \"{}\" failed to be created",
                 regex
             ),
+            Self::SecretScanningConfig(error) => write!(f, "The secret scanning is misconfigured: {}", error),
+            Self::IssueLookupConfig(error) => write!(f, "The issue lookup is misconfigured: {}", error),
+            Self::UserEmailsFile { path, error } => write!(f, "The user emails file \"{}\" failed to be read: {}", path, error),
         }
     }
 }
 
-#[derive(Deserialize, Debug)]
+/// Formats a size in bytes with binary units, e.g. "1.5 MiB".
+fn format_size(bytes: u64) -> String {
+    let units = ["bytes", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} bytes", bytes),
+        _ => format!("{:.1} {}", size, units[unit]),
+    }
+}
+
+impl ValidationError {
+    /// The name of the rule that failed, as it appears in the rules file.
+    pub fn rule(&self) -> &'static str {
+        match self {
+            Self::TitleFormat { .. } => "title_format",
+            Self::TitleMaxLength { .. } => "title_max_length",
+            Self::BodyRequired => "body_required",
+            Self::BodyMaxLineLength { .. } => "body_max_line_length",
+            Self::EnforceSquashMerge { .. } | Self::SquashMergeCommit { .. } => "enforce_squash_merge",
+            Self::MergeCommitNotAllowed { .. } => "linear_history",
+            Self::TooManyParents { .. } => "max_parents",
+            Self::MergeCommitRequired => "require_merge_commit",
+            Self::RefNameFormat { .. } => "ref_names",
+            Self::NonFastForward { .. } => "deny_non_fast_forward",
+            Self::RefDeletion { .. } => "deny_deletion",
+            Self::IdentityEmailDomain { .. }
+            | Self::IdentityEmailFormat { .. }
+            | Self::IdentityLocal { .. }
+            | Self::IdentityNameFormat { .. }
+            | Self::IdentityFullName { .. } => "identity",
+            Self::CommitterNotPusher { .. } | Self::UnknownPusher { .. } => "committer_must_match_pusher",
+            Self::UnsignedCommit | Self::UnverifiedSignature | Self::SignerMismatch { .. } => "require_signed_commits",
+            Self::SignedOffByMissing { .. } | Self::SignedOffByMismatch { .. } => "require_signed_off_by",
+            Self::ConventionalFormat { .. }
+            | Self::ConventionalType { .. }
+            | Self::ConventionalScopeMissing
+            | Self::ConventionalScope { .. }
+            | Self::ConventionalBreakingFooter
+            | Self::ConventionalBreakingMarker => "conventional_commits",
+            Self::TicketIdMissing { .. } | Self::TicketIdMismatch { .. } => "ticket_ids",
+            Self::FileTooLarge { .. } => "max_file_size",
+            Self::ForbiddenPath { .. } => "forbidden_paths",
+            Self::LfsPointerMissing { .. } | Self::LfsPointerInvalid { .. } | Self::LfsObjectMissing { .. } => "lfs",
+            Self::SecretDetected { .. } | Self::SecretScanningConfig(_) => "secret_scanning",
+            Self::IssueUnknown { .. } | Self::IssueClosed { .. } | Self::IssueLookupFailed { .. } => "issue_lookup",
+            Self::TrailerMissing { .. }
+            | Self::TrailerForbidden { .. }
+            | Self::TrailerFormat { .. }
+            | Self::TrailerNameEmail { .. } => "trailers",
+            Self::RegexCompilation(_) => "regex_compilation",
+            Self::UserEmailsFile { .. } => "user_emails_file",
+            Self::IssueLookupConfig(_) => "issue_lookup",
+        }
+    }
+}
+
+/// A non empty line of the commit body.
+/// The line number is counted from the start of the commit message, the title being line 1.
+#[derive(Debug, PartialEq)]
+pub struct BodyLine {
+    pub number: usize,
+    pub text: String,
+}
+
+/// The author or the committer of a commit.
+#[derive(Debug, PartialEq, Default)]
+pub struct Identity {
+    pub name: String,
+    pub email: String,
+}
+
+/// A "Key: value" line of the last paragraph of a commit message, e.g. "Signed-off-by: John Doe <john.doe@example.com>".
+/// The value of a trailer may continue in the next lines, so the trailer spans `lines` lines from line `number`.
+#[derive(Debug, PartialEq, Default)]
+pub struct Trailer {
+    pub key: String,
+    pub value: String,
+    pub number: usize,
+    pub lines: usize,
+}
+
+/// A pushed commit, with the parts of it that the validators need.
+#[derive(Debug, PartialEq, Default)]
+pub struct Commit {
+    pub id: String,
+    pub parents: Vec<String>,
+    pub author: Identity,
+    pub committer: Identity,
+    pub title: String,
+    pub body: Vec<BodyLine>,
+    pub trailers: Vec<Trailer>,
+    pub signed: bool,
+}
+
+/// A file that a commit adds ('A'), modifies ('M'), deletes ('D') or changes the type of ('T').
+/// The blobs and the modes of the missing sides are zeros.
+#[derive(Debug, PartialEq)]
+pub struct ChangedFile {
+    pub path: String,
+    pub status: char,
+    pub old_mode: String,
+    pub new_mode: String,
+    pub old_blob: String,
+    pub new_blob: String,
+}
+
+impl ChangedFile {
+    /// The new contents are a regular file or a symlink, i.e. not a deletion nor a submodule (gitlink).
+    pub fn has_new_blob(&self) -> bool { self.status != 'D' && self.new_mode != "160000" }
+}
+
+/// Warnings are reported without rejecting the push, and rules that are off are not reported at all.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity { #[default] Error, Warning, Off }
+
+/// A rule that a ref update failed. Rules about commits also have the commit that failed them.
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    pub ref_name: String,
+    pub commit_id: Option<String>,
+    pub commit_title: Option<String>,
+    pub error: ValidationError,
+    pub severity: Severity,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} (rule: {}, ref: {}", self.error, self.error.rule(), self.ref_name)?;
+        if let Some(commit_id) = &self.commit_id {
+            write!(f, ", commit: {}", commit_id)?;
+        }
+        if let Some(commit_title) = &self.commit_title {
+            write!(f, ", title: \"{}\"", commit_title)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Collects all the violations of a push, so that they are reported at once.
+#[derive(Debug, Default, PartialEq)]
+pub struct ValidationReport {
+    pub violations: Vec<Violation>,
+}
+
+impl ValidationReport {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn add(&mut self, ref_name: &str, commit: &Commit, error: ValidationError, severity: Severity) {
+        self.violations.push(Violation {
+            ref_name: ref_name.to_owned(),
+            commit_id: Some(commit.id.clone()),
+            commit_title: Some(commit.title.clone()),
+            error,
+            severity,
+        });
+    }
+
+    /// Adds a violation of a rule that is about the ref itself, and not about its commits.
+    pub fn add_ref_violation(&mut self, ref_name: &str, error: ValidationError, severity: Severity) {
+        self.violations.push(Violation {
+            ref_name: ref_name.to_owned(),
+            commit_id: None,
+            commit_title: None,
+            error,
+            severity,
+        });
+    }
+
+    pub fn extend(&mut self, other: ValidationReport) {
+        self.violations.extend(other.violations);
+    }
+
+    pub fn is_empty(&self) -> bool { self.violations.is_empty() }
+}
+
+/// A rule set, which applies to the branches that match its patterns.
+/// The branch patterns are globs (e.g. "release/*", "hotfix/**"), or regexes when prefixed with "regex:".
+#[derive(Deserialize, Debug, Default)]
 pub struct UpdateRules {
     pub branches: Option<Vec<String>>,
-    pub title_max_length: u8,
-    pub title_format: String,
+    pub title_max_length: Option<u8>,
+    pub title_format: Option<String>,
+    pub conventional_commits: Option<ConventionalCommitsRules>,
+    pub ticket_ids: Option<TicketIdRules>,
     pub body_required: Option<bool>,
     pub body_max_line_length: Option<u8>,
     pub enforce_squash_merge: Option<bool>,
+    pub deny_non_fast_forward: Option<bool>,
+    pub deny_deletion: Option<bool>,
+    /// The pushers that may still force push, when `deny_non_fast_forward` is enabled.
+    pub force_push_allowed_users: Option<Vec<String>>,
+    /// The commits must not be merge commits, e.g. on the main branch.
+    pub linear_history: Option<bool>,
+    /// The maximum number of parents of the commits, e.g. 2 to reject octopus merges.
+    pub max_parents: Option<u8>,
+    /// The commits on the first-parent chain of the branch must be merge commits, e.g. on the release branches.
+    pub require_merge_commit: Option<bool>,
+    pub identity: Option<IdentityRules>,
+    /// The committer email of each commit must be one of the emails of the pusher, in the user emails file.
+    pub committer_must_match_pusher: Option<bool>,
+    /// Commits of others are accepted too, when they have a "Pushed-by" trailer with the pusher.
+    pub allow_pushed_by_trailer: Option<bool>,
+    /// The commits must be signed by their committer, with a GPG key of the `gpg_home` keyring, or an SSH key of the `allowed_signers_file`.
+    pub require_signed_commits: Option<bool>,
+    /// The commits must have a "Signed-off-by" trailer by their author (Developer Certificate of Origin).
+    pub require_signed_off_by: Option<bool>,
+    pub trailers: Option<Vec<TrailerRule>>,
+    /// The maximum size in bytes of the files that the commits add or modify.
+    pub max_file_size: Option<u64>,
+    /// The maximum file sizes of paths, instead of `max_file_size`. The first matching override applies.
+    pub file_size_overrides: Option<Vec<FileSizeOverride>>,
+    /// The path globs that the commits must not add or modify.
+    pub forbidden_paths: Option<Vec<ForbiddenPath>>,
+    /// The files that must be committed as Git LFS pointers, instead of their contents.
+    pub lfs: Option<LfsRules>,
+    /// Scans the lines that the commits add for secrets, like keys and tokens.
+    pub secret_scanning: Option<SecretScanningRules>,
+    /// The severity of each rule, by rule name. Rules that are missing have error severity.
+    pub severity: Option<HashMap<String, Severity>>,
+}
+
+impl UpdateRules {
+    pub fn get_severity(&self, rule: &str) -> Severity {
+        self.severity
+            .as_ref()
+            .and_then(|severity| severity.get(rule).copied())
+            .unwrap_or_default()
+    }
+}
+
+/// The format that the names of new refs must have.
+#[derive(Deserialize, Debug)]
+pub struct RefNameRule {
+    pub format: String,
+    /// Explains the expected shape of the name to the user, e.g. "Use feature/<TICKET>-<description>".
+    pub description: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct RefNameRules {
+    pub branches: Option<RefNameRule>,
+    pub tags: Option<RefNameRule>,
+    /// The severity of the ref name violations, so that a naming policy can be rolled out as a warning first.
+    pub severity: Option<Severity>,
+}
+
+/// Rules for the author and the committer of each commit.
+#[derive(Deserialize, Debug, Default)]
+pub struct IdentityRules {
+    /// Email domain globs, e.g. "example.com" or "*.example.com".
+    pub allowed_email_domains: Option<Vec<String>>,
+    pub email_format: Option<String>,
+    pub name_format: Option<String>,
+    /// Requires a name of at least two words, e.g. "First Last".
+    pub require_full_name: Option<bool>,
+    /// Rejects identities that git makes up from the local machine, e.g. "root@localhost".
+    pub deny_local_identities: Option<bool>,
+}
+
+/// Rules for Conventional Commits messages, see https://www.conventionalcommits.org
+#[derive(Deserialize, Debug, Default)]
+pub struct ConventionalCommitsRules {
+    /// The allowed types. Defaults to the types of the Angular convention, e.g. "feat" and "fix".
+    pub types: Option<Vec<String>>,
+    /// The allowed scopes. Any scope is allowed if it is missing.
+    pub scopes: Option<Vec<String>>,
+    pub scope_required: Option<bool>,
+    /// A title with the "!" marker must have a "BREAKING CHANGE" footer too.
+    pub breaking_footer_required: Option<bool>,
+    /// A message with a "BREAKING CHANGE" footer must have the "!" marker in its title too.
+    pub breaking_marker_required: Option<bool>,
+}
+
+impl ConventionalCommitsRules {
+    pub fn get_types(&self) -> Vec<String> {
+        match &self.types {
+            Some(types) => types.clone(),
+            None => ["build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test"]
+                .iter()
+                .map(|commit_type| commit_type.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// The places where ticket IDs (e.g. "FOO-123") are looked for.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TicketIdLocation { Title, Body, Branch }
+
+impl std::fmt::Display for TicketIdLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Title => write!(f, "commit title"),
+            Self::Body => write!(f, "commit body"),
+            Self::Branch => write!(f, "branch name"),
+        }
+    }
+}
+
+/// Rules for the ticket IDs of the commit messages and the branch names.
+/// The patterns are regexes, where the first capture group is the ticket ID, or the whole match without groups.
+#[derive(Deserialize, Debug, Default)]
+pub struct TicketIdRules {
+    /// The default pattern of all the locations. Defaults to Jira like keys, e.g. "FOO-123".
+    pub pattern: Option<String>,
+    pub title_pattern: Option<String>,
+    pub body_pattern: Option<String>,
+    pub branch_pattern: Option<String>,
+    /// The locations that must have a ticket ID.
+    pub required_in: Option<Vec<TicketIdLocation>>,
+    /// When the branch name has ticket IDs, the commit message must mention one of them.
+    pub match_branch: Option<bool>,
+    /// Looks up the ticket IDs of the commit messages, to reject the unknown and the closed tickets.
+    pub lookup: Option<IssueLookupRules>,
+}
+
+/// The source of the issues, which is a local export of the issues, a Jira compatible REST endpoint, or both.
+#[derive(Deserialize, Debug, Default)]
+pub struct IssueLookupRules {
+    /// A JSON or CSV export of the issues.
+    pub index_file: Option<String>,
+    /// The issue endpoint, where "{key}" is replaced by the ticket ID, e.g.
+    /// "https://jira.example.com/rest/api/2/issue/{key}?fields=status".
+    pub url: Option<String>,
+    /// The environment variable with the bearer token of the endpoint.
+    pub token_variable: Option<String>,
+    /// Defaults to 5 seconds.
+    pub timeout_seconds: Option<u64>,
+    /// A JSON file that caches the responses of the endpoint across pushes.
+    pub cache_file: Option<String>,
+    /// Defaults to 1 hour.
+    pub cache_ttl_seconds: Option<u64>,
+    /// Accepts the tickets that could not be looked up, e.g. when the endpoint is down. Defaults to false.
+    pub fail_open: Option<bool>,
+    /// Defaults to "Closed", "Done" and "Resolved". The statuses are compared case insensitively.
+    pub closed_statuses: Option<Vec<String>>,
+}
+
+impl IssueLookupRules {
+    pub fn is_closed_status(&self, status: &str) -> bool {
+        match &self.closed_statuses {
+            Some(closed_statuses) => closed_statuses.iter().any(|closed_status| closed_status.eq_ignore_ascii_case(status)),
+            None => ["Closed", "Done", "Resolved"].iter().any(|closed_status| closed_status.eq_ignore_ascii_case(status)),
+        }
+    }
+}
+
+impl TicketIdRules {
+    pub fn get_pattern(&self, location: TicketIdLocation) -> &str {
+        let pattern = match location {
+            TicketIdLocation::Title => &self.title_pattern,
+            TicketIdLocation::Body => &self.body_pattern,
+            TicketIdLocation::Branch => &self.branch_pattern,
+        };
+        pattern.as_deref().or(self.pattern.as_deref()).unwrap_or(r"\b[A-Z][A-Z0-9]+-\d+\b")
+    }
+
+    pub fn is_required_in(&self, location: TicketIdLocation) -> bool {
+        self.required_in.as_ref().is_some_and(|locations| locations.contains(&location))
+    }
+}
+
+/// A path glob (e.g. "assets/**/*.png") with its own maximum file size. The files have no limit if it is missing.
+#[derive(Deserialize, Debug, Default)]
+pub struct FileSizeOverride {
+    pub path: String,
+    pub max_file_size: Option<u64>,
+}
+
+/// A path glob that the commits must not add or modify, e.g. "*.pem" or "dist/",
+/// optionally with the branch patterns that are allowed to have such files.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum ForbiddenPath {
+    Path(String),
+    WithExceptions { path: String, allowed_branches: Vec<String> },
+}
+
+impl ForbiddenPath {
+    pub fn get_path(&self) -> &str {
+        match self {
+            Self::Path(path) | Self::WithExceptions { path, .. } => path,
+        }
+    }
+
+    pub fn get_allowed_branches(&self) -> &[String] {
+        match self {
+            Self::Path(_) => &[],
+            Self::WithExceptions { allowed_branches, .. } => allowed_branches,
+        }
+    }
+}
+
+/// Rules for the files that must be stored with Git LFS, e.g. the binaries that would bloat the repository.
+#[derive(Deserialize, Debug, Default)]
+pub struct LfsRules {
+    /// The path globs of the files, e.g. "*.psd".
+    pub paths: Vec<String>,
+    /// The objects of the pointers must have been pushed to the LFS storage of the server.
+    pub require_objects: Option<bool>,
+    /// The directory of the LFS objects. Defaults to "lfs/objects" in the git directory.
+    pub objects_directory: Option<String>,
+}
+
+/// Rules for the scanning of the lines that the commits add, for secrets like keys and tokens.
+#[derive(Deserialize, Debug, Default)]
+pub struct SecretScanningRules {
+    /// The built-in detectors that are enabled. Defaults to all of them, except "high_entropy".
+    pub detectors: Option<Vec<String>>,
+    pub custom_patterns: Option<Vec<SecretPattern>>,
+    /// The minimum Shannon entropy (bits per character) of the "high_entropy" detector. Defaults to 4.5.
+    pub entropy_threshold: Option<f64>,
+    /// The path globs that are not scanned, e.g. test fixtures.
+    pub excluded_paths: Option<Vec<String>>,
+    /// A file with the fingerprints ("<commit>:<path>:<detector>:<line>") of the accepted false positives.
+    pub allowlist_file: Option<String>,
+}
+
+/// A custom secret detector. The secret is the first capture group of the regex, or its whole match without groups.
+#[derive(Deserialize, Debug, Default)]
+pub struct SecretPattern {
+    pub name: String,
+    pub regex: String,
+}
+
+/// A policy for the trailers with a key, e.g. "Reviewed-by". The keys are compared case insensitively.
+#[derive(Deserialize, Debug, Default)]
+pub struct TrailerRule {
+    pub key: String,
+    pub required: Option<bool>,
+    pub forbidden: Option<bool>,
+    /// A regex that the values must match.
+    pub format: Option<String>,
+    /// The values must be like "Name <email>".
+    pub name_email: Option<bool>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct Rules {
-    pub update: UpdateRules,
+    /// The rule sets, in order. Only the first one that matches a branch is applied to it.
+    #[serde(deserialize_with = "deserialize_rule_sets")]
+    pub update: Vec<UpdateRules>,
+    /// Checked only when a ref is created, so that existing refs can still be updated.
+    pub ref_names: Option<RefNameRules>,
+    /// The environment variable with the username of the pusher. Defaults to GitLab's "GL_USERNAME".
+    pub pusher_variable: Option<String>,
+    /// A JSON file that maps each username to its emails, e.g. {"jdoe": ["john.doe@example.com"]}.
+    pub user_emails_file: Option<String>,
+    /// The GnuPG home directory with the keyring that verifies GPG commit signatures.
+    pub gpg_home: Option<String>,
+    /// The SSH allowed signers file that verifies SSH commit signatures, see ssh-keygen(1).
+    pub allowed_signers_file: Option<String>,
+}
+
+/// A single rule set is accepted too, for rule files that do not need per branch rules.
+fn deserialize_rule_sets<'de, D>(deserializer: D) -> Result<Vec<UpdateRules>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RuleSets { One(Box<UpdateRules>), Many(Vec<UpdateRules>) }
+
+    match RuleSets::deserialize(deserializer)? {
+        RuleSets::One(rule_set) => Ok(vec![*rule_set]),
+        RuleSets::Many(rule_sets) => Ok(rule_sets),
+    }
 }
 
 impl Rules {
     pub fn new() -> Self {
         Self {
-            update: UpdateRules {
-                branches: None,
-                title_max_length: 80,
-                title_format: "".to_owned(),
-                body_required: None,
-                body_max_line_length: None,
-                enforce_squash_merge: None,
-            },
+            update: vec![UpdateRules {
+                title_max_length: Some(80),
+                ..Default::default()
+            }],
+            ref_names: None,
+            pusher_variable: None,
+            user_emails_file: None,
+            gpg_home: None,
+            allowed_signers_file: None,
         }
     }
+
+    /// Returns the username of the pusher, from the hook environment.
+    pub fn get_pusher(&self) -> Option<String> {
+        std::env::var(self.get_pusher_variable()).ok().filter(|pusher| !pusher.is_empty())
+    }
+
+    pub fn get_pusher_variable(&self) -> &str { self.pusher_variable.as_deref().unwrap_or("GL_USERNAME") }
 }
 
 impl Default for Rules { fn default() -> Self { Self::new() } }
 
 // Structs and implementations related to the Git hooks.
+/// Git uses the zero commit as the old commit of new refs, and as the new commit of deleted refs.
+#[derive(Deserialize, Debug, PartialEq)]
+pub enum RefChange { Create, Update, Delete }
+
 #[derive(Deserialize, Debug)]
 pub struct UpdateHookData {
     pub branch: String,
     pub new_commit: String,
     pub old_commit: String,
+    pub change: RefChange,
+}
+
+impl UpdateHookData {
+    pub fn new(ref_name: &str, old_commit: &str, new_commit: &str) -> Self {
+        let branch = ref_name.replace("refs/heads/", "");
+        let change = if is_zero_commit(old_commit) {
+            RefChange::Create
+        } else if is_zero_commit(new_commit) {
+            RefChange::Delete
+        } else {
+            RefChange::Update
+        };
+        Self { branch, new_commit: new_commit.to_owned(), old_commit: old_commit.to_owned(), change }
+    }
+}
+
+/// SHA-256 repositories use a longer zero commit, so only the characters are checked.
+fn is_zero_commit(commit: &str) -> bool {
+    !commit.is_empty() && commit.chars().all(|c| c == '0')
 }
 
 impl HookData for UpdateHookData {
     fn get_data(input: &str) -> Self {
         let parts: Vec<&str> = input.split(',').collect();
-        let branch = parts[0].replace("refs/heads/", "");
-        let old_commit = parts[1].to_owned();
-        let new_commit = parts[2].to_owned();
-        Self { branch, new_commit, old_commit }
+        Self::new(parts[0], parts[1], parts[2])
+    }
+}
+
+/// The pre-receive hook gets one "<old> <new> <refname>" line per pushed ref, through stdin.
+#[derive(Deserialize, Debug)]
+pub struct PreReceiveHookData {
+    pub updates: Vec<UpdateHookData>,
+}
+
+impl HookData for PreReceiveHookData {
+    fn get_data(input: &str) -> Self {
+        let updates = input
+            .lines()
+            .filter_map(|line| {
+                let parts: Vec<&str> = line.split_whitespace().collect();
+                match parts[..] {
+                    [old_commit, new_commit, ref_name] => {
+                        Some(UpdateHookData::new(ref_name, old_commit, new_commit))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        Self { updates }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 bytes");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(2 * 1024 * 1024 * 1024), "2.0 GiB");
+    }
+
+    #[test]
+    fn test_hook_from_path() {
+        assert!(matches!(Hook::from("/repo.git/custom_hooks/update"), Hook::Update));
+        assert!(matches!(Hook::from("/repo.git/custom_hooks/pre-receive"), Hook::PreReceive));
+        assert!(matches!(Hook::from(".git/hooks/commit-msg"), Hook::CommitMsg));
+        assert!(matches!(Hook::from("/repo.git/custom_hooks/post-receive"), Hook::Invalid));
+    }
+
+    #[test]
+    fn test_violation_display() {
+        let mut report = ValidationReport::new();
+        let commit = Commit {
+            id: "e418762".to_owned(),
+            title: "FOO-1: The title".to_owned(),
+            ..Default::default()
+        };
+        report.add(
+            "master",
+            &commit,
+            ValidationError::BodyMaxLineLength {
+                max_length: 10,
+                length: 14,
+                line: "The body line.".to_owned(),
+                line_number: 3,
+            },
+            Severity::Error,
+        );
+        assert_eq!(
+            report.violations[0].to_string(),
+            "Validation failed: The length of the body line 3 (\"The body line.\") is 14 characters, larger than the allowed 10 characters \
+            (rule: body_max_line_length, ref: master, commit: e418762, title: \"FOO-1: The title\")"
+        );
+
+        report.add_ref_violation(
+            "tmp2",
+            ValidationError::RefNameFormat {
+                ref_type: "branch".to_owned(),
+                ref_name: "tmp2".to_owned(),
+                pattern: "^feature/.+$".to_owned(),
+                description: None,
+            },
+            Severity::Error,
+        );
+        assert_eq!(
+            report.violations[1].to_string(),
+            "Validation failed: The branch name \"tmp2\" is not allowed. It must match the regex pattern: ^feature/.+$ \
+            (rule: ref_names, ref: tmp2)"
+        );
+    }
+
+    #[test]
+    fn test_rule_sets_deserialization() {
+        let rules: Rules = serde_json::from_str(r#"{"update": {"title_max_length": 50}}"#).unwrap();
+        assert_eq!(rules.update.len(), 1);
+        assert_eq!(rules.update[0].title_max_length, Some(50));
+
+        let rules: Rules = serde_json::from_str(
+            r#"{"update": [
+                {"branches": ["main"], "title_format": "^FOO-\\d+: .+$"},
+                {"branches": ["feature/*"], "title_max_length": 72}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(rules.update.len(), 2);
+        assert_eq!(rules.update[0].title_format.as_deref(), Some("^FOO-\\d+: .+$"));
+        assert_eq!(rules.update[1].branches, Some(vec!["feature/*".to_owned()]));
+    }
+
+    #[test]
+    fn test_get_severity() {
+        let rules: Rules = serde_json::from_str(
+            r#"{"update": {"severity": {"title_format": "warning", "body_required": "off"}}}"#,
+        )
+        .unwrap();
+        assert_eq!(rules.update[0].get_severity("title_format"), Severity::Warning);
+        assert_eq!(rules.update[0].get_severity("body_required"), Severity::Off);
+        assert_eq!(rules.update[0].get_severity("title_max_length"), Severity::Error);
+        assert_eq!(UpdateRules::default().get_severity("title_format"), Severity::Error);
+    }
+
+    #[test]
+    fn test_update_hook_data_change() {
+        let zero_commit = "0000000000000000000000000000000000000000";
+        let commit = "e418762e418762e418762e418762e418762e4187";
+        let data = UpdateHookData::get_data(&format!("refs/heads/master,{},{}", zero_commit, commit));
+        assert_eq!(data.change, RefChange::Create);
+        let data = UpdateHookData::get_data(&format!("refs/heads/master,{},{}", commit, zero_commit));
+        assert_eq!(data.change, RefChange::Delete);
+        let data = UpdateHookData::get_data(&format!("refs/heads/master,{},{}", commit, commit));
+        assert_eq!(data.change, RefChange::Update);
+
+        assert!(is_zero_commit("0000000000000000000000000000000000000000000000000000000000000000"));
+        assert!(!is_zero_commit(""));
+    }
+
+    #[test]
+    fn test_pre_receive_hook_data() {
+        let input = "1111111111111111111111111111111111111111 2222222222222222222222222222222222222222 refs/heads/master
+3333333333333333333333333333333333333333 4444444444444444444444444444444444444444 refs/heads/feature/foo
+
+";
+        let data = PreReceiveHookData::get_data(input);
+        assert_eq!(data.updates.len(), 2);
+        assert_eq!(data.updates[0].branch, "master");
+        assert_eq!(data.updates[0].old_commit, "1111111111111111111111111111111111111111");
+        assert_eq!(data.updates[0].new_commit, "2222222222222222222222222222222222222222");
+        assert_eq!(data.updates[1].branch, "feature/foo");
+        assert_eq!(data.updates[1].old_commit, "3333333333333333333333333333333333333333");
+        assert_eq!(data.updates[1].new_commit, "4444444444444444444444444444444444444444");
     }
 }
@@ -1,51 +1,394 @@
+use crate::git::{
+    get_commit_signer, get_git_dir, is_ancestor, verify_commit, CatFileBatch, CatFileBatchCheck,
+    DiffTreeBatch,
+};
+use crate::issues::IssueTracker;
 use crate::loggers::log_to_file;
-use crate::structs::{UpdateHookData, UpdateRules, ValidationError};
+use crate::secrets::{mask_secret, SecretScanner};
+use crate::structs::{
+    BodyLine, ChangedFile, Commit, ConventionalCommitsRules, Identity, IdentityRules,
+    IssueLookupRules, PreReceiveHookData, RefChange, RefNameRules, Rules, Severity,
+    TicketIdLocation, TicketIdRules, Trailer, TrailerRule, UpdateHookData, UpdateRules,
+    ValidationError, ValidationReport,
+};
 use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::process::{exit, Command};
 
 // Public functions
+/// Validates all the commits of a ref update and reports every violation found.
+/// An error is returned only when the rules themselves are invalid.
 pub fn validate_update_rules(
-    hook_rules: &UpdateRules,
+    rules: &Rules,
     hook_data: &UpdateHookData,
-) -> Result<(), ValidationError> {
-    if hook_rules.branches.is_some() {
-        // Do not run any validation if the current branch is not in the list of provided branches.
-        if !hook_rules
-            .branches
-            .as_ref()
-            .unwrap()
-            .contains(&hook_data.branch)
-        {
-            return Ok(());
+) -> Result<ValidationReport, ValidationError> {
+    let mut report = ValidationReport::new();
+
+    // The names of existing refs are not validated, so that they can still be updated.
+    if let Some(ref_name_rules) = &rules.ref_names {
+        let severity = ref_name_rules.severity.unwrap_or_default();
+        if hook_data.change == RefChange::Create && severity != Severity::Off {
+            if let Some(e) = _validate_ref_name(ref_name_rules, &hook_data.branch)? {
+                report.add_ref_violation(&hook_data.branch, e, severity);
+            }
         }
     }
 
-    let commits_range: Vec<String> =
-        _get_commits_range(&hook_data.old_commit, &hook_data.new_commit);
-    let commits: Vec<String> = _get_commits(&commits_range);
-    let commit_titles: Vec<String> = _get_commit_titles(&commits);
-    let commit_bodies = _get_commit_bodies(&commits);
+    // Do not run any commit validation if no rule set applies to the current branch.
+    let hook_rules = match get_branch_rules(&rules.update, &hook_data.branch)? {
+        Some(v) => v,
+        None => return Ok(report),
+    };
+
+    // A deleted ref does not bring any commits, so there is nothing else to validate.
+    if hook_data.change == RefChange::Delete {
+        if let Some(true) = hook_rules.deny_deletion {
+            let e = ValidationError::RefDeletion {
+                ref_name: hook_data.branch.clone(),
+            };
+            _add_ref_violation(&mut report, hook_rules, &hook_data.branch, e);
+        }
+        return Ok(report);
+    }
 
-    let title_regex_validator = create_regex(&hook_rules.title_format)?;
+    if let Some(true) = hook_rules.deny_non_fast_forward {
+        if let Err(e) = _validate_fast_forward(hook_rules, hook_data, rules.get_pusher()) {
+            _add_ref_violation(&mut report, hook_rules, &hook_data.branch, e);
+        }
+    }
 
-    // Title related validations.
-    validate_title_format(&commit_titles, &title_regex_validator)?;
-    _validate_title_max_length(&commit_titles, hook_rules.title_max_length)?;
+    // The pusher and their emails, when the committers must match the pusher.
+    let mut pusher_emails = None;
+    if let Some(true) = hook_rules.committer_must_match_pusher {
+        match rules.get_pusher() {
+            Some(pusher) => {
+                let emails = _get_user_emails(rules.user_emails_file.as_deref(), &pusher)?;
+                pusher_emails = Some((pusher, emails));
+            }
+            None => {
+                let e = ValidationError::UnknownPusher {
+                    variable: rules.get_pusher_variable().to_owned(),
+                };
+                _add_ref_violation(&mut report, hook_rules, &hook_data.branch, e);
+            }
+        }
+    }
 
-    if hook_rules.body_required.is_some() {
-        _validate_body_required(&commit_bodies)?;
+    // The validators compile the regexes and globs of the rules once, for all the commits of the push.
+    let title_regex_validator = _create_title_regex(hook_rules)?;
+    let trailers_validator = _create_trailers_validator(hook_rules)?;
+    let mut ticket_id_validator = _create_ticket_id_validator(hook_rules, &hook_data.branch)?;
+    if let Some(ticket_id_validator) = &ticket_id_validator {
+        if let Some(e) = ticket_id_validator.validate_branch() {
+            _add_ref_violation(&mut report, hook_rules, &hook_data.branch, e);
+        }
+    }
+    let identity_validator = match &hook_rules.identity {
+        Some(identity_rules) => Some(IdentityValidator::new(identity_rules)?),
+        None => None,
+    };
+    let file_size_validator = FileSizeValidator::new(hook_rules)?;
+    let lfs_validator = LfsValidator::new(hook_rules)?;
+    let mut cat_file_check = match file_size_validator.is_some() || lfs_validator.is_some() {
+        true => Some(_get_cat_file_batch_check()),
+        false => None,
+    };
+    let forbidden_paths_validator = ForbiddenPathsValidator::new(hook_rules, &hook_data.branch)?;
+    let secret_scanner = match &hook_rules.secret_scanning {
+        Some(secret_scanning_rules) => Some(SecretScanner::new(secret_scanning_rules)?),
+        None => None,
     };
+    let mut diff_tree = match file_size_validator.is_some()
+        || lfs_validator.is_some()
+        || forbidden_paths_validator.is_some()
+        || secret_scanner.is_some()
+    {
+        true => Some(_get_diff_tree_batch(secret_scanner.is_some())),
+        false => None,
+    };
+    let commits_range: Vec<String> = _get_commits_range(&hook_data.new_commit, false);
+    // The commits that the push adds directly to the branch, and not to the repository before. The commits of the
+    // merged branches are not on the first-parent chain, so they do not have to be merges themselves.
+    let first_parent_chain: HashSet<String> = match hook_rules.require_merge_commit {
+        Some(true) => _get_commits_range(&hook_data.new_commit, true)
+            .into_iter()
+            .collect(),
+        _ => HashSet::new(),
+    };
+
+    // The commits are read one by one, so that memory usage stays flat regardless of the push size.
+    let mut cat_file = _get_cat_file_batch();
+    for (index, commit_hash) in commits_range.iter().enumerate() {
+        let commit = _get_commit(&mut cat_file, commit_hash);
+        let mut errors = _validate_message(
+            hook_rules,
+            title_regex_validator.as_ref(),
+            trailers_validator.as_ref(),
+            &commit.title,
+            &commit.body,
+            &commit.trailers,
+        );
+
+        if let Some(ticket_id_validator) = &mut ticket_id_validator {
+            errors.extend(ticket_id_validator.validate_message(&commit.title, &commit.body));
+        }
 
-    if hook_rules.body_max_line_length.is_some() {
-        _validate_body_max_line_length(&commit_bodies, hook_rules.body_max_line_length.unwrap())?;
+        // The files of merge commits are the ones that the merge changes itself, e.g. in the conflict resolutions.
+        if let Some(diff_tree) = &mut diff_tree {
+            let mut secret_errors = vec![];
+            let changed_files = _read_diff(diff_tree, &commit, |path, line_number, text| {
+                if let Some(secret_scanner) = &secret_scanner {
+                    secret_errors.extend(_scan_line_for_secrets(
+                        secret_scanner,
+                        &commit,
+                        path,
+                        line_number,
+                        text,
+                    ));
+                }
+            });
+            if let Some(forbidden_paths_validator) = &forbidden_paths_validator {
+                errors.extend(forbidden_paths_validator.validate(&changed_files));
+            }
+            if let (Some(file_size_validator), Some(cat_file_check)) =
+                (&file_size_validator, &mut cat_file_check)
+            {
+                errors.extend(file_size_validator.validate(cat_file_check, &changed_files));
+            }
+            if let (Some(lfs_validator), Some(cat_file_check)) =
+                (&lfs_validator, &mut cat_file_check)
+            {
+                errors.extend(lfs_validator.validate(
+                    cat_file_check,
+                    &mut cat_file,
+                    &changed_files,
+                ));
+            }
+            errors.extend(secret_errors);
+        }
+
+        if let Some(true) = hook_rules.enforce_squash_merge {
+            // The number of commits is reported once, on the newest commit of the push.
+            if index == 0 {
+                if let Err(e) = _validator_enforce_squash_merge(&commits_range) {
+                    errors.push(e);
+                }
+            }
+            if let Err(e) = _validate_not_merge_commit(&commit.parents) {
+                errors.push(e);
+            }
+        }
+
+        errors.extend(_validate_parents(hook_rules, &commit.parents));
+        // The first commit of the repository has no parent to merge.
+        if first_parent_chain.contains(&commit.id) && commit.parents.len() == 1 {
+            errors.push(ValidationError::MergeCommitRequired);
+        }
+
+        if let Some(identity_validator) = &identity_validator {
+            errors.extend(identity_validator.validate("author", &commit.author));
+            errors.extend(identity_validator.validate("committer", &commit.committer));
+        }
+
+        if let Some(true) = hook_rules.require_signed_commits {
+            if let Err(e) = _validate_commit_signature(rules, &commit) {
+                errors.push(e);
+            }
+        }
+
+        if let Some(true) = hook_rules.require_signed_off_by {
+            if let Err(e) = _validate_signed_off_by(&commit) {
+                errors.push(e);
+            }
+        }
+
+        if let Some((pusher, emails)) = &pusher_emails {
+            if let Err(e) = _validate_committer_is_pusher(hook_rules, pusher, emails, &commit) {
+                errors.push(e);
+            }
+        }
+
+        for (error, severity) in _apply_severities(hook_rules, errors) {
+            report.add(&hook_data.branch, &commit, error, severity);
+        }
     }
 
-    // Todo: Pending.
-    // if let Some(true) = hook_rules.enforce_squash_merge {
-    //     _validator_enforce_squash_merge(&commits_range)?;
-    // }
+    Ok(report)
+}
 
-    Ok(())
+/// Validates a commit message that is not committed yet (commit-msg hook),
+/// against the rule set of the branch that it is committed to.
+pub fn validate_commit_msg_rules(
+    rules: &Rules,
+    branch: &str,
+    commit_message: &str,
+) -> Result<Vec<(ValidationError, Severity)>, ValidationError> {
+    let hook_rules = match get_branch_rules(&rules.update, branch)? {
+        Some(v) => v,
+        None => return Ok(vec![]),
+    };
+    let title_regex_validator = _create_title_regex(hook_rules)?;
+    let trailers_validator = _create_trailers_validator(hook_rules)?;
+    let commit_title = _get_message_title(commit_message);
+    let commit_body = _get_message_body(commit_message);
+    let mut errors = _validate_message(
+        hook_rules,
+        title_regex_validator.as_ref(),
+        trailers_validator.as_ref(),
+        &commit_title,
+        &commit_body,
+        &_get_message_trailers(commit_message),
+    );
+    if let Some(mut ticket_id_validator) = _create_ticket_id_validator(hook_rules, branch)? {
+        // A detached HEAD has no branch to check.
+        if !branch.is_empty() {
+            errors.extend(ticket_id_validator.validate_branch());
+        }
+        errors.extend(ticket_id_validator.validate_message(&commit_title, &commit_body));
+    }
+    Ok(_apply_severities(hook_rules, errors))
+}
+
+/// Adds a violation of a rule set rule that is about the ref, with the severity of the rule.
+fn _add_ref_violation(
+    report: &mut ValidationReport,
+    hook_rules: &UpdateRules,
+    ref_name: &str,
+    error: ValidationError,
+) {
+    let severity = hook_rules.get_severity(error.rule());
+    if severity != Severity::Off {
+        report.add_ref_violation(ref_name, error, severity);
+    }
+}
+
+/// Pairs each error with the severity of its rule, and drops the errors of the rules that are off.
+fn _apply_severities(
+    hook_rules: &UpdateRules,
+    errors: Vec<ValidationError>,
+) -> Vec<(ValidationError, Severity)> {
+    errors
+        .into_iter()
+        .map(|error| {
+            let severity = hook_rules.get_severity(error.rule());
+            (error, severity)
+        })
+        .filter(|(_, severity)| *severity != Severity::Off)
+        .collect()
+}
+
+/// Runs all the title and body validations, and returns all the failures.
+fn _validate_message(
+    hook_rules: &UpdateRules,
+    title_regex_validator: Option<&Regex>,
+    trailers_validator: Option<&TrailersValidator>,
+    commit_title: &str,
+    commit_body: &[BodyLine],
+    commit_trailers: &[Trailer],
+) -> Vec<ValidationError> {
+    let mut errors = vec![];
+
+    // Title related validations.
+    if let Some(title_regex_validator) = title_regex_validator {
+        if let Err(e) = validate_title_format(commit_title, title_regex_validator) {
+            errors.push(e);
+        }
+    }
+    if let Some(title_max_length) = hook_rules.title_max_length {
+        if let Err(e) = _validate_title_max_length(commit_title, title_max_length) {
+            errors.push(e);
+        }
+    }
+
+    if let Some(conventional_commits_rules) = &hook_rules.conventional_commits {
+        errors.extend(validate_conventional_commit(
+            conventional_commits_rules,
+            commit_title,
+            commit_body,
+        ));
+    }
+
+    // Body related validations.
+    if let Some(true) = hook_rules.body_required {
+        if let Err(e) = _validate_body_required(commit_body) {
+            errors.push(e);
+        }
+    }
+    if let Some(body_max_line_length) = hook_rules.body_max_line_length {
+        errors.extend(_validate_body_max_line_length(
+            commit_body,
+            commit_trailers,
+            body_max_line_length,
+        ));
+    }
+
+    // Trailer related validations.
+    if let Some(trailers_validator) = trailers_validator {
+        errors.extend(trailers_validator.validate(commit_trailers));
+    }
+
+    errors
+}
+
+/// Validates all the refs of a push against the update rules.
+/// The pre-receive hook accepts or rejects the push as a whole.
+pub fn validate_pre_receive_rules(
+    rules: &Rules,
+    hook_data: &PreReceiveHookData,
+) -> Result<ValidationReport, ValidationError> {
+    let mut report = ValidationReport::new();
+    for update_hook_data in &hook_data.updates {
+        report.extend(validate_update_rules(rules, update_hook_data)?);
+    }
+    Ok(report)
+}
+
+/// Validates the name of a branch or a tag. Other refs (e.g. notes) are not validated.
+/// The outer error is returned when the format regex is invalid.
+fn _validate_ref_name(
+    ref_name_rules: &RefNameRules,
+    ref_name: &str,
+) -> Result<Option<ValidationError>, ValidationError> {
+    let (ref_type, ref_name_rule, name) = match ref_name.strip_prefix("refs/tags/") {
+        Some(tag) => ("tag", &ref_name_rules.tags, tag),
+        None if !ref_name.starts_with("refs/") => ("branch", &ref_name_rules.branches, ref_name),
+        None => return Ok(None),
+    };
+    let ref_name_rule = match ref_name_rule {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+
+    if create_regex(&ref_name_rule.format)?.is_match(name) {
+        return Ok(None);
+    }
+    Ok(Some(ValidationError::RefNameFormat {
+        ref_type: ref_type.to_owned(),
+        ref_name: name.to_owned(),
+        pattern: ref_name_rule.format.clone(),
+        description: ref_name_rule.description.clone(),
+    }))
+}
+
+/// Returns the first rule set that applies to the branch.
+/// A rule set without branches applies to all of them.
+pub fn get_branch_rules<'a>(
+    rule_sets: &'a [UpdateRules],
+    branch: &str,
+) -> Result<Option<&'a UpdateRules>, ValidationError> {
+    for rule_set in rule_sets {
+        let branches = match &rule_set.branches {
+            Some(v) => v,
+            None => return Ok(Some(rule_set)),
+        };
+        for pattern in branches {
+            if create_branch_regex(pattern)?.is_match(branch) {
+                return Ok(Some(rule_set));
+            }
+        }
+    }
+    Ok(None)
 }
 
 pub fn create_regex(regex_str: &str) -> Result<Regex, crate::ValidationError> {
@@ -57,159 +400,1169 @@ pub fn create_regex(regex_str: &str) -> Result<Regex, crate::ValidationError> {
     }
 }
 
+/// Branch patterns are globs, or regexes when prefixed with "regex:".
+pub fn create_branch_regex(pattern: &str) -> Result<Regex, ValidationError> {
+    match pattern.strip_prefix("regex:") {
+        Some(regex_str) => create_regex(regex_str),
+        None => create_glob_regex(pattern),
+    }
+}
+
+/// Converts a glob to a regex. "*" and "?" do not match "/", while "**" matches any characters.
+pub fn create_glob_regex(glob: &str) -> Result<Regex, ValidationError> {
+    let mut regex_str = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // "**/" also matches zero directories.
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex_str.push_str("(?:.*/)?");
+                } else {
+                    regex_str.push_str(".*");
+                }
+            }
+            '*' => regex_str.push_str("[^/]*"),
+            '?' => regex_str.push_str("[^/]"),
+            _ => regex_str.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex_str.push('$');
+    create_regex(&regex_str)
+}
+
+/// Converts a path glob to a regex. Like in .gitignore, a glob without "/" matches the file name
+/// in any directory, e.g. "*.sql" matches "dumps/2023.sql", and a glob that ends with "/"
+/// matches everything in the directory, e.g. "dist/" matches "web/dist/app.js".
+pub fn create_path_glob_regex(glob: &str) -> Result<Regex, ValidationError> {
+    let (glob, directory) = match glob.strip_suffix('/') {
+        Some(glob) => (glob, true),
+        None => (glob, false),
+    };
+    let mut regex_glob = match glob.strip_prefix('/') {
+        Some(glob) => glob.to_owned(),
+        None if glob.contains('/') => glob.to_owned(),
+        None => format!("**/{}", glob),
+    };
+    if directory {
+        regex_glob.push_str("/**");
+    }
+    create_glob_regex(&regex_glob)
+}
+
+fn _create_title_regex(hook_rules: &UpdateRules) -> Result<Option<Regex>, ValidationError> {
+    hook_rules
+        .title_format
+        .as_deref()
+        .map(create_regex)
+        .transpose()
+}
+
 // Private functions.
 pub fn validate_title_format(
-    commit_titles: &[String],
+    commit_title: &str,
     regex_validator: &regex::Regex,
 ) -> Result<(), ValidationError> {
-    for commit_title in commit_titles {
-        if !regex_validator.is_match(commit_title) {
-            return Err(ValidationError::TitleFormat(format!(
-                "{:?}",
-                regex_validator
-            )));
-        }
+    if !regex_validator.is_match(commit_title) {
+        return Err(ValidationError::TitleFormat {
+            pattern: format!("{:?}", regex_validator),
+            title: commit_title.to_owned(),
+        });
     }
 
     Ok(())
 }
 
-fn _validate_body_required(commit_bodies: &[Vec<String>]) -> Result<(), ValidationError> {
-    for commit_body in commit_bodies {
-        if commit_body.is_empty() {
-            return Err(ValidationError::BodyRequired);
+/// Validates a commit message against the Conventional Commits specification,
+/// i.e. a "<type>[(<scope>)][!]: <description>" title and an optional "BREAKING CHANGE: <description>" footer.
+pub fn validate_conventional_commit(
+    rules: &ConventionalCommitsRules,
+    commit_title: &str,
+    commit_body: &[BodyLine],
+) -> Vec<ValidationError> {
+    let mut errors = vec![];
+    let title = match _parse_conventional_title(commit_title) {
+        Some(v) => v,
+        None => {
+            errors.push(ValidationError::ConventionalFormat {
+                title: commit_title.to_owned(),
+            });
+            return errors;
         }
+    };
+
+    let types = rules.get_types();
+    if !types
+        .iter()
+        .any(|commit_type| commit_type == title.commit_type)
+    {
+        errors.push(ValidationError::ConventionalType {
+            commit_type: title.commit_type.to_owned(),
+            types,
+        });
     }
-    Ok(())
+
+    match title.scope {
+        Some(scope) => {
+            if let Some(scopes) = &rules.scopes {
+                if !scopes.iter().any(|allowed_scope| allowed_scope == scope) {
+                    errors.push(ValidationError::ConventionalScope {
+                        scope: scope.to_owned(),
+                        scopes: scopes.clone(),
+                    });
+                }
+            }
+        }
+        None => {
+            if let Some(true) = rules.scope_required {
+                errors.push(ValidationError::ConventionalScopeMissing);
+            }
+        }
+    }
+
+    // The footers are in the last paragraph, like the trailers.
+    let breaking_footer = _get_last_paragraph(commit_body).iter().any(|line| {
+        line.text.starts_with("BREAKING CHANGE:") || line.text.starts_with("BREAKING-CHANGE:")
+    });
+    if title.breaking && !breaking_footer && rules.breaking_footer_required == Some(true) {
+        errors.push(ValidationError::ConventionalBreakingFooter);
+    }
+    if breaking_footer && !title.breaking && rules.breaking_marker_required == Some(true) {
+        errors.push(ValidationError::ConventionalBreakingMarker);
+    }
+
+    errors
 }
 
-fn _get_commits(commits_range: &[String]) -> Vec<String> {
-    commits_range
-        .iter()
-        .map(|commit_hash| _get_commit(commit_hash))
-        .collect()
+/// Returns the last paragraph of the body, which is the one that `_get_message_trailers` parses the trailers from.
+/// The body lines do not include the empty lines, so a paragraph ends where the line numbers skip.
+fn _get_last_paragraph(commit_body: &[BodyLine]) -> &[BodyLine] {
+    let start = (1..commit_body.len())
+        .rev()
+        .find(|index| commit_body[*index].number != commit_body[index - 1].number + 1)
+        .unwrap_or(0);
+    &commit_body[start..]
 }
 
-/// Extracts the full commit, from a commit hash.
-fn _get_commit(commit_hash: &str) -> String {
-    let output = match Command::new("git")
-        .arg("cat-file")
-        .arg("commit")
-        .arg(commit_hash)
-        .output()
+/// The parts of a "<type>[(<scope>)][!]: <description>" title.
+struct ConventionalTitle<'a> {
+    commit_type: &'a str,
+    scope: Option<&'a str>,
+    breaking: bool,
+}
+
+fn _parse_conventional_title(title: &str) -> Option<ConventionalTitle<'_>> {
+    let (head, description) = title.split_once(": ")?;
+    if description.trim().is_empty() {
+        return None;
+    }
+
+    let (head, breaking) = match head.strip_suffix('!') {
+        Some(head) => (head, true),
+        None => (head, false),
+    };
+    let (commit_type, scope) = match head.strip_suffix(')') {
+        Some(head) => {
+            let (commit_type, scope) = head.split_once('(')?;
+            if scope.trim().is_empty() || scope.contains(['(', ')']) {
+                return None;
+            }
+            (commit_type, Some(scope))
+        }
+        None => (head, None),
+    };
+    if commit_type.is_empty()
+        || commit_type.contains(|c: char| c.is_whitespace() || "()!:".contains(c))
     {
+        return None;
+    }
+
+    Some(ConventionalTitle {
+        commit_type,
+        scope,
+        breaking,
+    })
+}
+
+fn _validate_body_required(commit_body: &[BodyLine]) -> Result<(), ValidationError> {
+    if commit_body.is_empty() {
+        return Err(ValidationError::BodyRequired);
+    }
+    Ok(())
+}
+
+fn _get_cat_file_batch() -> CatFileBatch {
+    match CatFileBatch::new() {
         Ok(v) => v,
-        Err(_e) => {
-            let _ =
-                log_to_file("_get_commit(): Failed to execute git cat-file commit <commit_hash>.");
+        Err(e) => {
+            let _ = log_to_file(&format!(
+                "_get_cat_file_batch(): Failed to execute git cat-file --batch: {}",
+                e
+            ));
             exit(1);
         }
-    };
+    }
+}
 
-    match String::from_utf8(output.stdout) {
+fn _get_cat_file_batch_check() -> CatFileBatchCheck {
+    match CatFileBatchCheck::new() {
         Ok(v) => v,
-        Err(_e) => {
-            let _ =
-                log_to_file("_get_commit(): Failed to get utf8 string from git cat-file output");
+        Err(e) => {
+            let _ = log_to_file(&format!(
+                "_get_cat_file_batch_check(): Failed to execute git cat-file --batch-check: {}",
+                e
+            ));
             exit(1);
         }
     }
 }
 
-fn _get_commit_bodies(commits: &[String]) -> Vec<Vec<String>> {
-    commits
-        .iter()
-        .map(|commit_hash| _get_commit_body(commit_hash))
+fn _get_git_dir() -> PathBuf {
+    match get_git_dir() {
+        Ok(v) => PathBuf::from(v),
+        Err(e) => {
+            let _ = log_to_file(&format!("_get_git_dir(): {}", e));
+            exit(1);
+        }
+    }
+}
+
+fn _get_diff_tree_batch(with_patches: bool) -> DiffTreeBatch {
+    match DiffTreeBatch::new(with_patches) {
+        Ok(v) => v,
+        Err(e) => {
+            let _ = log_to_file(&format!(
+                "_get_diff_tree_batch(): Failed to execute git diff-tree --stdin: {}",
+                e
+            ));
+            exit(1);
+        }
+    }
+}
+
+/// Returns the files that a commit adds, modifies or deletes, compared to its parent,
+/// and calls the function for each line that it adds, when the patches are read.
+fn _read_diff<F>(
+    diff_tree: &mut DiffTreeBatch,
+    commit: &Commit,
+    on_added_line: F,
+) -> Vec<ChangedFile>
+where
+    F: FnMut(&str, usize, &str),
+{
+    match diff_tree.read_diff(&commit.id, on_added_line) {
+        Ok(v) => v,
+        Err(e) => {
+            let _ = log_to_file(&format!("_read_diff(): {}", e));
+            exit(1);
+        }
+    }
+}
+
+/// Reads a commit through the cat-file process, and extracts the parts that the validators need.
+fn _get_commit(cat_file: &mut CatFileBatch, commit_hash: &str) -> Commit {
+    let commit = match cat_file.read_commit(commit_hash) {
+        Ok(v) => v,
+        Err(e) => {
+            let _ = log_to_file(&format!("_get_commit(): {}", e));
+            exit(1);
+        }
+    };
+
+    Commit {
+        id: commit_hash.to_owned(),
+        parents: _get_commit_parents(&commit),
+        author: _get_commit_identity(&commit, "author"),
+        committer: _get_commit_identity(&commit, "committer"),
+        title: _get_commit_title(&commit),
+        body: _get_commit_body(&commit),
+        trailers: _get_message_trailers(&_get_commit_message(&commit)),
+        signed: _is_commit_signed(&commit),
+    }
+}
+
+/// Checks if a full commit has a signature header, "gpgsig" (GPG or SSH) or "gpgsig-sha256".
+fn _is_commit_signed(commit: &str) -> bool {
+    commit
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .any(|line| line.starts_with("gpgsig ") || line.starts_with("gpgsig-sha256 "))
+}
+
+/// Extracts the parent hashes from the headers of a full commit.
+fn _get_commit_parents(commit: &str) -> Vec<String> {
+    commit
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .filter_map(|line| line.strip_prefix("parent "))
+        .map(|parent| parent.trim().to_owned())
         .collect()
 }
 
+/// Extracts the identity of a header ("author" or "committer") of a full commit.
+/// The header is like "author John Doe <john.doe@gmail.com> 1690000000 +0300".
+fn _get_commit_identity(commit: &str, header: &str) -> Identity {
+    let prefix = format!("{} ", header);
+    let value = match commit
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .find_map(|line| line.strip_prefix(&prefix))
+    {
+        Some(v) => v,
+        None => return Identity::default(),
+    };
+    _parse_identity(value)
+}
+
+/// Parses an identity like "John Doe <john.doe@example.com>", ignoring anything after the email.
+fn _parse_identity(value: &str) -> Identity {
+    match (value.find('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => Identity {
+            name: value[..start].trim().to_owned(),
+            email: value[start + 1..end].trim().to_owned(),
+        },
+        _ => Identity {
+            name: value.trim().to_owned(),
+            email: "".to_owned(),
+        },
+    }
+}
+
 /// Extracts the commit body from a full commit.
-fn _get_commit_body(commit: &str) -> Vec<String> {
+fn _get_commit_body(commit: &str) -> Vec<BodyLine> {
+    _get_message_body(&_get_commit_message(commit))
+}
+
+/// Extracts the commit message (title and body) from a full commit.
+/// The message comes after the first empty line, which separates it from the headers.
+/// Multiline headers (e.g. "gpgsig") have their empty lines indented, so they never end the headers.
+fn _get_commit_message(commit: &str) -> String {
+    commit
+        .lines()
+        .skip_while(|line| !line.is_empty())
+        .skip(1)
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Extracts the body lines from a commit message.
+fn _get_message_body(message: &str) -> Vec<BodyLine> {
     let mut body_lines = vec![];
-    let mut first_empty_line_found = false;
-    let mut second_empty_line_found = false;
+    let mut title_found = false;
+    let mut empty_line_found = false;
 
-    // Start saving the commit lines that come after the second empty line.
-    // The second empty line comes after the commit title and before the commit body.
-    for line in commit.lines() {
+    // Start saving the message lines that come after the empty line that follows the title.
+    for (index, line) in message.lines().enumerate() {
         let line = line.trim();
 
-        if line.is_empty() && !first_empty_line_found {
-            first_empty_line_found = true;
+        if !line.is_empty() && !title_found {
+            title_found = true;
             continue;
         }
 
-        if line.is_empty() && first_empty_line_found {
-            second_empty_line_found = true;
+        if line.is_empty() && title_found {
+            empty_line_found = true;
             continue;
         }
 
-        if !line.is_empty() && second_empty_line_found {
-            body_lines.push(line.to_owned());
-            continue;
+        if !line.is_empty() && empty_line_found {
+            body_lines.push(BodyLine {
+                number: index + 1,
+                text: line.to_owned(),
+            });
         }
     }
 
     body_lines
 }
 
-fn _get_commit_titles(commits: &[String]) -> Vec<String> {
-    commits
+/// Extracts the trailers from a commit message, with the rules of git interpret-trailers.
+/// The trailers are the last paragraph of the message, when all its lines are "Key: value" lines
+/// or indented continuations of them. A paragraph with a "Signed-off-by" or a cherry-pick line
+/// needs only a quarter of its lines to be trailers, and its other lines are ignored.
+fn _get_message_trailers(message: &str) -> Vec<Trailer> {
+    let lines: Vec<&str> = message.lines().collect();
+    let end = match lines.iter().rposition(|line| !line.trim().is_empty()) {
+        Some(v) => v,
+        None => return vec![],
+    };
+    let start = lines[..end]
         .iter()
-        .map(|commit| _get_commit_title(commit))
-        .collect()
-}
+        .rposition(|line| line.trim().is_empty())
+        .map_or(0, |index| index + 1);
 
-/// Extracts the commit title from a full commit message.
-fn _get_commit_title(commit: &str) -> String {
-    let mut title = "";
-    let mut found_first_empty_line = false;
+    // The title paragraph is never a trailer paragraph.
+    if lines[..start].iter().all(|line| line.trim().is_empty()) {
+        return vec![];
+    }
 
-    for line in commit.lines() {
-        let line = line.trim();
-        if line.is_empty() && !found_first_empty_line {
-            found_first_empty_line = true;
+    let mut trailers: Vec<Trailer> = vec![];
+    let mut trailer_lines = 0;
+    let mut non_trailer_lines = 0;
+    let mut recognized_prefix = false;
+    let mut in_trailer = false;
+    for (index, line) in lines.iter().enumerate().take(end + 1).skip(start) {
+        if line.starts_with(char::is_whitespace) {
+            match trailers.last_mut() {
+                Some(trailer) if in_trailer => {
+                    trailer.value.push(' ');
+                    trailer.value.push_str(line.trim());
+                    trailer.lines += 1;
+                }
+                _ => non_trailer_lines += 1,
+            }
+            continue;
         }
-        if !line.is_empty() && found_first_empty_line {
-            title = line;
-            break;
+
+        in_trailer = false;
+        if line.starts_with("(cherry picked from commit ") {
+            recognized_prefix = true;
+            trailer_lines += 1;
+            continue;
+        }
+        match _parse_trailer_line(line) {
+            Some((key, value)) => {
+                recognized_prefix |= key.eq_ignore_ascii_case("Signed-off-by");
+                trailer_lines += 1;
+                in_trailer = true;
+                trailers.push(Trailer {
+                    key: key.to_owned(),
+                    value: value.to_owned(),
+                    number: index + 1,
+                    lines: 1,
+                });
+            }
+            None => non_trailer_lines += 1,
         }
     }
 
-    title.to_owned()
+    if (trailer_lines > 0 && non_trailer_lines == 0)
+        || (recognized_prefix && trailer_lines * 3 >= non_trailer_lines)
+    {
+        return trailers;
+    }
+    vec![]
+}
+
+/// Splits a "Key: value" trailer line. The key has only letters, digits and dashes,
+/// and it may be followed by spaces before the colon.
+fn _parse_trailer_line(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.split_once(':')?;
+    let key = key.trim_end();
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return None;
+    }
+    Some((key, value.trim()))
+}
+
+/// Extracts the commit title from a full commit.
+fn _get_commit_title(commit: &str) -> String {
+    _get_message_title(&_get_commit_message(commit))
+}
+
+/// Extracts the title from a commit message, which is its first non empty line.
+fn _get_message_title(message: &str) -> String {
+    message
+        .lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or("")
+        .to_owned()
 }
 
 fn _validate_title_max_length(
-    commit_titles: &[String],
+    commit_title: &str,
     max_title_length: u8,
 ) -> Result<(), ValidationError> {
-    for title in commit_titles.iter() {
-        let number_of_characters = title.chars().count();
-        if number_of_characters > max_title_length as usize {
-            return Err(ValidationError::TitleMaxLength(max_title_length));
-        }
+    let number_of_characters = commit_title.chars().count();
+    if number_of_characters > max_title_length as usize {
+        return Err(ValidationError::TitleMaxLength {
+            max_length: max_title_length,
+            length: number_of_characters,
+            title: commit_title.to_owned(),
+        });
     }
     Ok(())
 }
 
-fn _get_commits_range(old_commit: &str, new_commit: &str) -> Vec<String> {
-    // Todo: This implementation does not correctly get all the commits.
-    // Todo: Check the correct way to get all the commits.
-    // Todo: Get all commits from current branch and remove the ones that exists in the target branch
-    // git rev-list HEAD (and remove the ones that exists in the target branch)
-    // git rev-list target_branch..HEAD (git rev-list master..HEAD)
-    let commit_range = format!("{}..{}", old_commit, new_commit);
-    let output = match Command::new("git")
-        .arg("rev-list")
-        .arg(commit_range)
-        .output()
-    {
+/// Checks the email domains, the formats and the shape of the author and committer identities.
+struct IdentityValidator<'a> {
+    rules: &'a IdentityRules,
+    email_domain_regexes: Vec<Regex>,
+    email_regex: Option<Regex>,
+    name_regex: Option<Regex>,
+}
+
+impl<'a> IdentityValidator<'a> {
+    fn new(rules: &'a IdentityRules) -> Result<Self, ValidationError> {
+        let email_domain_regexes = rules
+            .allowed_email_domains
+            .iter()
+            .flatten()
+            .map(|domain| create_glob_regex(&domain.to_lowercase()))
+            .collect::<Result<Vec<Regex>, ValidationError>>()?;
+        Ok(Self {
+            rules,
+            email_domain_regexes,
+            email_regex: rules
+                .email_format
+                .as_deref()
+                .map(create_regex)
+                .transpose()?,
+            name_regex: rules.name_format.as_deref().map(create_regex).transpose()?,
+        })
+    }
+
+    /// Validates the identity of a role ("author" or "committer") of a commit.
+    fn validate(&self, role: &str, identity: &Identity) -> Vec<ValidationError> {
+        let mut errors = vec![];
+        let domain = match identity.email.rsplit_once('@') {
+            Some((_, domain)) => domain.to_lowercase(),
+            None => "".to_owned(),
+        };
+
+        if let Some(true) = self.rules.deny_local_identities {
+            if _is_local_email_domain(&domain) {
+                errors.push(ValidationError::IdentityLocal {
+                    role: role.to_owned(),
+                    email: identity.email.clone(),
+                });
+            }
+        }
+
+        if let Some(allowed_domains) = &self.rules.allowed_email_domains {
+            if !self
+                .email_domain_regexes
+                .iter()
+                .any(|regex| regex.is_match(&domain))
+            {
+                errors.push(ValidationError::IdentityEmailDomain {
+                    role: role.to_owned(),
+                    email: identity.email.clone(),
+                    allowed_domains: allowed_domains.clone(),
+                });
+            }
+        }
+
+        if let Some(email_regex) = &self.email_regex {
+            if !email_regex.is_match(&identity.email) {
+                errors.push(ValidationError::IdentityEmailFormat {
+                    role: role.to_owned(),
+                    email: identity.email.clone(),
+                    pattern: email_regex.to_string(),
+                });
+            }
+        }
+
+        if let Some(name_regex) = &self.name_regex {
+            if !name_regex.is_match(&identity.name) {
+                errors.push(ValidationError::IdentityNameFormat {
+                    role: role.to_owned(),
+                    name: identity.name.clone(),
+                    pattern: name_regex.to_string(),
+                });
+            }
+        }
+
+        if let Some(true) = self.rules.require_full_name {
+            if !_is_full_name(&identity.name) {
+                errors.push(ValidationError::IdentityFullName {
+                    role: role.to_owned(),
+                    name: identity.name.clone(),
+                });
+            }
+        }
+
+        errors
+    }
+}
+
+/// The trailer policies by key: the required and forbidden keys, and the formats of the values.
+struct TrailersValidator<'a> {
+    rules: &'a [TrailerRule],
+    format_regexes: Vec<Option<Regex>>,
+    name_email_regex: Regex,
+}
+
+fn _create_trailers_validator(
+    hook_rules: &UpdateRules,
+) -> Result<Option<TrailersValidator<'_>>, ValidationError> {
+    match &hook_rules.trailers {
+        Some(trailer_rules) => Ok(Some(TrailersValidator::new(trailer_rules)?)),
+        None => Ok(None),
+    }
+}
+
+impl<'a> TrailersValidator<'a> {
+    fn new(rules: &'a [TrailerRule]) -> Result<Self, ValidationError> {
+        let format_regexes = rules
+            .iter()
+            .map(|rule| rule.format.as_deref().map(create_regex).transpose())
+            .collect::<Result<Vec<Option<Regex>>, ValidationError>>()?;
+        Ok(Self {
+            rules,
+            format_regexes,
+            name_email_regex: create_regex(r"^[^<>@]*[^<>@\s] <[^<>@\s]+@[^<>@\s]+\.[^<>@\s]+>$")?,
+        })
+    }
+
+    /// Validates the trailers of a commit. The keys are compared case insensitively, like git does.
+    fn validate(&self, trailers: &[Trailer]) -> Vec<ValidationError> {
+        let mut errors = vec![];
+        for (rule, format_regex) in self.rules.iter().zip(&self.format_regexes) {
+            let values: Vec<&str> = trailers
+                .iter()
+                .filter(|trailer| trailer.key.eq_ignore_ascii_case(&rule.key))
+                .map(|trailer| trailer.value.as_str())
+                .collect();
+
+            if values.is_empty() {
+                if let Some(true) = rule.required {
+                    errors.push(ValidationError::TrailerMissing {
+                        key: rule.key.clone(),
+                    });
+                }
+                continue;
+            }
+            if let Some(true) = rule.forbidden {
+                errors.push(ValidationError::TrailerForbidden {
+                    key: rule.key.clone(),
+                });
+                continue;
+            }
+
+            for value in values {
+                if let Some(format_regex) = format_regex {
+                    if !format_regex.is_match(value) {
+                        errors.push(ValidationError::TrailerFormat {
+                            key: rule.key.clone(),
+                            value: value.to_owned(),
+                            pattern: format_regex.to_string(),
+                        });
+                    }
+                }
+                if let Some(true) = rule.name_email {
+                    if !self.name_email_regex.is_match(value) {
+                        errors.push(ValidationError::TrailerNameEmail {
+                            key: rule.key.clone(),
+                            value: value.to_owned(),
+                        });
+                    }
+                }
+            }
+        }
+        errors
+    }
+}
+
+/// The default size limit of the files, and the per path overrides that raise, lower or remove it.
+struct FileSizeValidator {
+    max_file_size: Option<u64>,
+    overrides: Vec<(Regex, Option<u64>)>,
+}
+
+impl FileSizeValidator {
+    /// Returns `None` when the rule set has no file size limits.
+    fn new(hook_rules: &UpdateRules) -> Result<Option<Self>, ValidationError> {
+        if hook_rules.max_file_size.is_none() && hook_rules.file_size_overrides.is_none() {
+            return Ok(None);
+        }
+        let overrides = hook_rules
+            .file_size_overrides
+            .iter()
+            .flatten()
+            .map(|file_size_override| {
+                Ok((
+                    create_path_glob_regex(&file_size_override.path)?,
+                    file_size_override.max_file_size,
+                ))
+            })
+            .collect::<Result<Vec<(Regex, Option<u64>)>, ValidationError>>()?;
+        Ok(Some(Self {
+            max_file_size: hook_rules.max_file_size,
+            overrides,
+        }))
+    }
+
+    fn get_max_file_size(&self, path: &str) -> Option<u64> {
+        match self
+            .overrides
+            .iter()
+            .find(|(regex, _)| regex.is_match(path))
+        {
+            Some((_, max_file_size)) => *max_file_size,
+            None => self.max_file_size,
+        }
+    }
+
+    fn validate(
+        &self,
+        cat_file_check: &mut CatFileBatchCheck,
+        changed_files: &[ChangedFile],
+    ) -> Vec<ValidationError> {
+        let mut errors = vec![];
+        for changed_file in changed_files.iter().filter(|file| file.has_new_blob()) {
+            let max_size = match self.get_max_file_size(&changed_file.path) {
+                Some(v) => v,
+                None => continue,
+            };
+            let size = match cat_file_check.read_size(&changed_file.new_blob) {
+                Ok(v) => v,
+                Err(e) => {
+                    let _ = log_to_file(&format!("FileSizeValidator::validate(): {}", e));
+                    exit(1);
+                }
+            };
+            if size > max_size {
+                errors.push(ValidationError::FileTooLarge {
+                    path: changed_file.path.clone(),
+                    size,
+                    max_size,
+                });
+            }
+        }
+        errors
+    }
+}
+
+/// Scans a line that a commit adds for secrets. The secrets are masked in the errors,
+/// which have the fingerprints to allowlist the false positives with.
+fn _scan_line_for_secrets(
+    secret_scanner: &SecretScanner,
+    commit: &Commit,
+    path: &str,
+    line_number: usize,
+    text: &str,
+) -> Vec<ValidationError> {
+    if secret_scanner.is_excluded(path) {
+        return vec![];
+    }
+    let mut errors = vec![];
+    for secret in secret_scanner.scan_line(text) {
+        let fingerprint = format!("{}:{}:{}:{}", commit.id, path, secret.detector, line_number);
+        if secret_scanner.is_allowed(&fingerprint) {
+            continue;
+        }
+        errors.push(ValidationError::SecretDetected {
+            path: path.to_owned(),
+            line_number,
+            detector: secret.detector,
+            masked_secret: mask_secret(&secret.value),
+            fingerprint,
+        });
+    }
+    errors
+}
+
+/// The forbidden path globs of a branch, without the ones that the branch is an exception of.
+struct ForbiddenPathsValidator {
+    patterns: Vec<(String, Regex)>,
+}
+
+impl ForbiddenPathsValidator {
+    /// Returns `None` when no forbidden path applies to the branch.
+    fn new(hook_rules: &UpdateRules, branch: &str) -> Result<Option<Self>, ValidationError> {
+        let mut patterns = vec![];
+        for forbidden_path in hook_rules.forbidden_paths.iter().flatten() {
+            let mut allowed = false;
+            for allowed_branch in forbidden_path.get_allowed_branches() {
+                allowed |= create_branch_regex(allowed_branch)?.is_match(branch);
+            }
+            if !allowed {
+                let path = forbidden_path.get_path();
+                patterns.push((path.to_owned(), create_path_glob_regex(path)?));
+            }
+        }
+
+        if patterns.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self { patterns }))
+    }
+
+    /// Deleting a forbidden file is allowed, so that it can be cleaned up.
+    fn validate(&self, changed_files: &[ChangedFile]) -> Vec<ValidationError> {
+        changed_files
+            .iter()
+            .filter(|changed_file| changed_file.status != 'D')
+            .filter_map(|changed_file| {
+                let (pattern, _) = self
+                    .patterns
+                    .iter()
+                    .find(|(_, regex)| regex.is_match(&changed_file.path))?;
+                Some(ValidationError::ForbiddenPath {
+                    path: changed_file.path.clone(),
+                    pattern: pattern.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Git LFS pointer files are smaller than 1024 bytes, so that larger blobs are not pointers.
+const LFS_POINTER_MAX_SIZE: u64 = 1024;
+
+/// The paths that must be Git LFS pointers, and the LFS storage that their objects must be in.
+struct LfsValidator {
+    patterns: Vec<(String, Regex)>,
+    /// The LFS storage, when the objects of the pointers must exist.
+    objects_directory: Option<PathBuf>,
+}
+
+impl LfsValidator {
+    /// Returns `None` when the rule set has no LFS rules.
+    fn new(hook_rules: &UpdateRules) -> Result<Option<Self>, ValidationError> {
+        let lfs_rules = match &hook_rules.lfs {
+            Some(v) => v,
+            None => return Ok(None),
+        };
+        let patterns = lfs_rules
+            .paths
+            .iter()
+            .map(|path| Ok((path.clone(), create_path_glob_regex(path)?)))
+            .collect::<Result<Vec<(String, Regex)>, ValidationError>>()?;
+        let objects_directory = match (lfs_rules.require_objects, &lfs_rules.objects_directory) {
+            (Some(true), Some(objects_directory)) => Some(PathBuf::from(objects_directory)),
+            (Some(true), None) => Some(_get_git_dir().join("lfs").join("objects")),
+            _ => None,
+        };
+        Ok(Some(Self {
+            patterns,
+            objects_directory,
+        }))
+    }
+
+    /// The blobs of the matching files must be LFS pointers, which are read only when they are small enough.
+    fn validate(
+        &self,
+        cat_file_check: &mut CatFileBatchCheck,
+        cat_file: &mut CatFileBatch,
+        changed_files: &[ChangedFile],
+    ) -> Vec<ValidationError> {
+        let mut errors = vec![];
+        // Symbolic links are not stored with LFS.
+        for changed_file in changed_files
+            .iter()
+            .filter(|file| file.has_new_blob() && file.new_mode != "120000")
+        {
+            let pattern = match self
+                .patterns
+                .iter()
+                .find(|(_, regex)| regex.is_match(&changed_file.path))
+            {
+                Some((pattern, _)) => pattern,
+                None => continue,
+            };
+            let contents = match cat_file_check.read_size(&changed_file.new_blob) {
+                Ok(size) if size >= LFS_POINTER_MAX_SIZE => None,
+                Ok(_) => match cat_file.read_object(&changed_file.new_blob) {
+                    Ok((_, contents)) => Some(contents),
+                    Err(e) => {
+                        let _ = log_to_file(&format!("LfsValidator::validate(): {}", e));
+                        exit(1);
+                    }
+                },
+                Err(e) => {
+                    let _ = log_to_file(&format!("LfsValidator::validate(): {}", e));
+                    exit(1);
+                }
+            };
+            let contents = match contents {
+                Some(v) if v.starts_with(b"version ") => v,
+                _ => {
+                    errors.push(ValidationError::LfsPointerMissing {
+                        path: changed_file.path.clone(),
+                        pattern: pattern.clone(),
+                    });
+                    continue;
+                }
+            };
+
+            let (oid, size) = match parse_lfs_pointer(&contents) {
+                Ok(v) => v,
+                Err(reason) => {
+                    errors.push(ValidationError::LfsPointerInvalid {
+                        path: changed_file.path.clone(),
+                        reason,
+                    });
+                    continue;
+                }
+            };
+            if let Some(objects_directory) = &self.objects_directory {
+                // The objects are stored like "lfs/objects/4d/7a/4d7a2146...".
+                let object_path = objects_directory
+                    .join(&oid[0..2])
+                    .join(&oid[2..4])
+                    .join(&oid);
+                match std::fs::metadata(object_path) {
+                    Ok(metadata) if metadata.len() == size => {}
+                    _ => errors.push(ValidationError::LfsObjectMissing {
+                        path: changed_file.path.clone(),
+                        oid,
+                    }),
+                }
+            }
+        }
+        errors
+    }
+}
+
+/// Parses a Git LFS pointer file (see https://github.com/git-lfs/git-lfs/blob/main/docs/spec.md),
+/// and returns the SHA-256 hash and the size of its object.
+fn parse_lfs_pointer(contents: &[u8]) -> Result<(String, u64), String> {
+    let contents = match std::str::from_utf8(contents) {
+        Ok(v) => v,
+        Err(_) => return Err("it is not UTF-8".to_owned()),
+    };
+    let contents = match contents.strip_suffix('\n') {
+        Some(v) => v,
+        None => return Err("it does not end with a newline".to_owned()),
+    };
+
+    let mut oid = None;
+    let mut size = None;
+    let mut previous_key = "";
+    for (index, line) in contents.split('\n').enumerate() {
+        let (key, value) = match line.split_once(' ') {
+            Some(v) => v,
+            None => return Err(format!("the line \"{}\" is not a key and a value", line)),
+        };
+        if index == 0 {
+            if line != "version https://git-lfs.github.com/spec/v1" {
+                return Err(format!("the version \"{}\" is not supported", value));
+            }
+            continue;
+        }
+        // The keys after the version are sorted.
+        if key <= previous_key {
+            return Err(format!("the key \"{}\" is not in order", key));
+        }
+        previous_key = key;
+        match key {
+            "oid" => {
+                let hash = value.strip_prefix("sha256:").unwrap_or_default();
+                if hash.len() != 64 || !hash.chars().all(|c| matches!(c, '0'..='9' | 'a'..='f')) {
+                    return Err(format!("the oid \"{}\" is not a SHA-256 hash", value));
+                }
+                oid = Some(hash.to_owned());
+            }
+            "size" => match value.parse::<u64>() {
+                Ok(v) if value.chars().all(|c| c.is_ascii_digit()) => size = Some(v),
+                _ => return Err(format!("the size \"{}\" is not a number", value)),
+            },
+            _ => {}
+        }
+    }
+    match (oid, size) {
+        (Some(oid), Some(size)) => Ok((oid, size)),
+        (None, _) => Err("the oid is missing".to_owned()),
+        (_, None) => Err("the size is missing".to_owned()),
+    }
+}
+
+/// Extracts the ticket IDs of the branch and the commit messages, and looks them up in the issue tracker.
+struct TicketIdValidator<'a> {
+    rules: &'a TicketIdRules,
+    issue_tracker: Option<IssueTracker<'a>>,
+    branch: String,
+    branch_ids: Vec<String>,
+    title_regex: Regex,
+    body_regex: Regex,
+}
+
+fn _create_ticket_id_validator<'a>(
+    hook_rules: &'a UpdateRules,
+    branch: &str,
+) -> Result<Option<TicketIdValidator<'a>>, ValidationError> {
+    match &hook_rules.ticket_ids {
+        Some(ticket_id_rules) => Ok(Some(TicketIdValidator::new(ticket_id_rules, branch)?)),
+        None => Ok(None),
+    }
+}
+
+impl<'a> TicketIdValidator<'a> {
+    fn new(rules: &'a TicketIdRules, branch: &str) -> Result<Self, ValidationError> {
+        let branch_regex = create_regex(rules.get_pattern(TicketIdLocation::Branch))?;
+        let issue_tracker = match &rules.lookup {
+            Some(lookup_rules) => Some(IssueTracker::new(lookup_rules)?),
+            None => None,
+        };
+        Ok(Self {
+            rules,
+            issue_tracker,
+            branch: branch.to_owned(),
+            branch_ids: _extract_ticket_ids(&branch_regex, branch),
+            title_regex: create_regex(rules.get_pattern(TicketIdLocation::Title))?,
+            body_regex: create_regex(rules.get_pattern(TicketIdLocation::Body))?,
+        })
+    }
+
+    /// Tags are not branches, so they never need a ticket ID.
+    fn validate_branch(&self) -> Option<ValidationError> {
+        if self.branch_ids.is_empty()
+            && self.rules.is_required_in(TicketIdLocation::Branch)
+            && !self.branch.starts_with("refs/tags/")
+        {
+            return Some(ValidationError::TicketIdMissing {
+                location: TicketIdLocation::Branch.to_string(),
+            });
+        }
+        None
+    }
+
+    fn validate_message(
+        &mut self,
+        commit_title: &str,
+        commit_body: &[BodyLine],
+    ) -> Vec<ValidationError> {
+        let mut errors = vec![];
+        let title_ids = _extract_ticket_ids(&self.title_regex, commit_title);
+        let body_text = commit_body
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let body_ids = _extract_ticket_ids(&self.body_regex, &body_text);
+
+        for (location, ids) in [
+            (TicketIdLocation::Title, &title_ids),
+            (TicketIdLocation::Body, &body_ids),
+        ] {
+            if ids.is_empty() && self.rules.is_required_in(location) {
+                errors.push(ValidationError::TicketIdMissing {
+                    location: location.to_string(),
+                });
+            }
+        }
+
+        // A message without ticket IDs is left to the required locations.
+        let mut message_ids = title_ids;
+        for id in body_ids {
+            if !message_ids.contains(&id) {
+                message_ids.push(id);
+            }
+        }
+        if let Some(true) = self.rules.match_branch {
+            if !self.branch_ids.is_empty()
+                && !message_ids.is_empty()
+                && !message_ids.iter().any(|id| self.branch_ids.contains(id))
+            {
+                errors.push(ValidationError::TicketIdMismatch {
+                    branch: self.branch.clone(),
+                    branch_ids: self.branch_ids.clone(),
+                    message_ids: message_ids.clone(),
+                });
+            }
+        }
+
+        if let (Some(issue_tracker), Some(lookup_rules)) =
+            (&mut self.issue_tracker, &self.rules.lookup)
+        {
+            for id in &message_ids {
+                if let Some(e) = _validate_issue(issue_tracker, lookup_rules, id) {
+                    errors.push(e);
+                }
+            }
+        }
+        errors
+    }
+}
+
+/// Rejects the tickets that do not exist or are closed. The tickets that could not be looked up
+/// are rejected too, unless the lookup fails open.
+fn _validate_issue(
+    issue_tracker: &mut IssueTracker,
+    lookup_rules: &IssueLookupRules,
+    key: &str,
+) -> Option<ValidationError> {
+    match issue_tracker.lookup(key) {
+        Ok(Some(status)) if lookup_rules.is_closed_status(&status) => {
+            Some(ValidationError::IssueClosed {
+                key: key.to_owned(),
+                status,
+            })
+        }
+        Ok(Some(_)) => None,
+        Ok(None) => Some(ValidationError::IssueUnknown {
+            key: key.to_owned(),
+        }),
+        Err(reason) if lookup_rules.fail_open == Some(true) => {
+            let _ = log_to_file(&format!(
+                "_validate_issue(): The ticket \"{}\" is accepted, because its lookup failed: {}",
+                key, reason
+            ));
+            None
+        }
+        Err(reason) => Some(ValidationError::IssueLookupFailed {
+            key: key.to_owned(),
+            reason,
+        }),
+    }
+}
+
+/// Extracts the unique ticket IDs of a text, which are the first capture group of the regex,
+/// or its whole match when it has no groups.
+fn _extract_ticket_ids(regex: &Regex, text: &str) -> Vec<String> {
+    let mut ids: Vec<String> = vec![];
+    for captures in regex.captures_iter(text) {
+        let id = match captures.get(1).or_else(|| captures.get(0)) {
+            Some(v) => v.as_str().to_owned(),
+            None => continue,
+        };
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+/// Git makes up the email from the user and the host name when it is not configured,
+/// which results in emails like "root@localhost" or "user@laptop.(none)".
+fn _is_local_email_domain(domain: &str) -> bool {
+    let local_suffixes = [".(none)", ".local", ".localdomain", ".lan", ".home"];
+    domain.is_empty()
+        || domain == "localhost"
+        || !domain.contains('.')
+        || local_suffixes.iter().any(|suffix| domain.ends_with(suffix))
+}
+
+/// A full name has at least two words, where the first does not start with a lowercase letter.
+/// The other words may, like the surname particles ("Ludwig van Beethoven"), and scripts without case are accepted ("李 小龙").
+fn _is_full_name(name: &str) -> bool {
+    let words: Vec<&str> = name.split_whitespace().collect();
+    words.len() >= 2
+        && words
+            .first()
+            .and_then(|word| word.chars().next())
+            .is_some_and(|c| !c.is_lowercase())
+}
+
+/// Returns the commits that a ref update brings into the repository, newest first, or only the ones
+/// of its first-parent chain. Commits that are already reachable from any ref are skipped, since they have been validated before.
+fn _get_commits_range(new_commit: &str, first_parent: bool) -> Vec<String> {
+    // The refs are not updated yet while the hook runs, so "--not --all" also excludes
+    // the old commit of the ref and its ancestors. This works for new refs (zero old commit) too.
+    let mut command = Command::new("git");
+    command.arg("rev-list");
+    if first_parent {
+        command.arg("--first-parent");
+    }
+    let output = match command.arg(new_commit).arg("--not").arg("--all").output() {
         Ok(v) => v,
         Err(_e) => {
             let _ = log_to_file("_get_commits_range(): Failed to execute git rev-list");
             exit(1);
         }
     };
+    if !output.status.success() {
+        let _ = log_to_file(&format!(
+            "_get_commits_range(): git rev-list failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+        exit(1);
+    }
     let output_string = match String::from_utf8(output.stdout) {
         Ok(v) => v,
         Err(_e) => {
@@ -217,111 +1570,500 @@ fn _get_commits_range(old_commit: &str, new_commit: &str) -> Vec<String> {
             exit(1);
         }
     };
-    output_string
-        .lines()
-        .into_iter()
-        .map(|line| line.to_owned())
+    output_string.lines().map(|line| line.to_owned()).collect()
+}
+
+/// Rejects updates where the new commit does not descend from the old one (force pushes),
+/// unless the pusher is allowed to force push.
+fn _validate_fast_forward(
+    hook_rules: &UpdateRules,
+    hook_data: &UpdateHookData,
+    pusher: Option<String>,
+) -> Result<(), ValidationError> {
+    // Creations and deletions do not rewrite any history.
+    if hook_data.change != RefChange::Update {
+        return Ok(());
+    }
+
+    if let (Some(pusher), Some(allowed_users)) = (pusher, &hook_rules.force_push_allowed_users) {
+        if allowed_users.contains(&pusher) {
+            return Ok(());
+        }
+    }
+
+    let fast_forward = match is_ancestor(&hook_data.old_commit, &hook_data.new_commit) {
+        Ok(v) => v,
+        Err(e) => {
+            let _ = log_to_file(&format!("_validate_fast_forward(): {}", e));
+            exit(1);
+        }
+    };
+    if !fast_forward {
+        return Err(ValidationError::NonFastForward {
+            old_commit: hook_data.old_commit.clone(),
+            new_commit: hook_data.new_commit.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Rejects unsigned commits, and commits whose signature does not verify
+/// against the configured GPG keyring or SSH allowed signers.
+fn _validate_commit_signature(rules: &Rules, commit: &Commit) -> Result<(), ValidationError> {
+    if !commit.signed {
+        return Err(ValidationError::UnsignedCommit);
+    }
+
+    let verified = match verify_commit(
+        &commit.id,
+        rules.gpg_home.as_deref(),
+        rules.allowed_signers_file.as_deref(),
+    ) {
+        Ok(v) => v,
+        Err(e) => {
+            let _ = log_to_file(&format!("_validate_commit_signature(): {}", e));
+            exit(1);
+        }
+    };
+    if !verified {
+        return Err(ValidationError::UnverifiedSignature);
+    }
+
+    // A valid signature of another user's key does not vouch for the commit.
+    let signer = match get_commit_signer(
+        &commit.id,
+        rules.gpg_home.as_deref(),
+        rules.allowed_signers_file.as_deref(),
+    ) {
+        Ok(v) => v,
+        Err(e) => {
+            let _ = log_to_file(&format!("_validate_commit_signature(): {}", e));
+            exit(1);
+        }
+    };
+    if !_get_signer_email(&signer).eq_ignore_ascii_case(&commit.committer.email) {
+        return Err(ValidationError::SignerMismatch {
+            signer,
+            committer_email: commit.committer.email.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Returns the email of a GPG user ID ("Name <email>"), or an SSH principal as is.
+fn _get_signer_email(signer: &str) -> &str {
+    match (signer.rfind('<'), signer.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &signer[start + 1..end],
+        _ => signer,
+    }
+}
+
+/// Requires a "Signed-off-by" trailer by the author of the commit (Developer Certificate of Origin).
+/// The names and the emails are compared case insensitively.
+fn _validate_signed_off_by(commit: &Commit) -> Result<(), ValidationError> {
+    let sign_offs: Vec<&str> = commit
+        .trailers
+        .iter()
+        .filter(|trailer| trailer.key.eq_ignore_ascii_case("Signed-off-by"))
+        .map(|trailer| trailer.value.as_str())
+        .collect();
+    if sign_offs.is_empty() {
+        return Err(ValidationError::SignedOffByMissing {
+            author: format!("{} <{}>", commit.author.name, commit.author.email),
+        });
+    }
+
+    let signed_off_by_author = sign_offs.iter().any(|sign_off| {
+        let identity = _parse_identity(sign_off);
+        identity.name.to_lowercase() == commit.author.name.to_lowercase()
+            && identity.email.eq_ignore_ascii_case(&commit.author.email)
+    });
+    if !signed_off_by_author {
+        return Err(ValidationError::SignedOffByMismatch {
+            author: format!("{} <{}>", commit.author.name, commit.author.email),
+            sign_offs: sign_offs
+                .iter()
+                .map(|sign_off| sign_off.to_string())
+                .collect(),
+        });
+    }
+    Ok(())
+}
+
+/// Reads the emails of a user from the user emails file, which maps each username to its emails.
+/// A user that is missing from the file has no emails.
+fn _get_user_emails(path: Option<&str>, user: &str) -> Result<Vec<String>, ValidationError> {
+    let path =
+        match path {
+            Some(v) => v,
+            None => return Err(ValidationError::UserEmailsFile {
+                path: "".to_owned(),
+                error:
+                    "The \"user_emails_file\" rule is required by \"committer_must_match_pusher\""
+                        .to_owned(),
+            }),
+        };
+    let file_error = |error: String| ValidationError::UserEmailsFile {
+        path: path.to_owned(),
+        error,
+    };
+
+    let contents = std::fs::read_to_string(path).map_err(|e| file_error(e.to_string()))?;
+    let mut user_emails: HashMap<String, Vec<String>> =
+        serde_json::from_str(&contents).map_err(|e| file_error(e.to_string()))?;
+    Ok(user_emails.remove(user).unwrap_or_default())
+}
+
+/// Rejects commits that the pusher did not commit, unless they have a "Pushed-by" trailer
+/// with the username or an email of the pusher, and such trailers are allowed.
+fn _validate_committer_is_pusher(
+    hook_rules: &UpdateRules,
+    pusher: &str,
+    pusher_emails: &[String],
+    commit: &Commit,
+) -> Result<(), ValidationError> {
+    let is_pusher_email = |email: &str| {
+        pusher_emails
+            .iter()
+            .any(|pusher_email| pusher_email.eq_ignore_ascii_case(email.trim()))
+    };
+    if is_pusher_email(&commit.committer.email) {
+        return Ok(());
+    }
+
+    let pushed_by_allowed = hook_rules.allow_pushed_by_trailer == Some(true);
+    if pushed_by_allowed {
+        let pushed_by_pusher = commit
+            .trailers
+            .iter()
+            .filter(|trailer| trailer.key.eq_ignore_ascii_case("Pushed-by"))
+            .any(|trailer| {
+                // The value is like "jdoe", "john.doe@example.com" or "John Doe <john.doe@example.com>".
+                trailer.value == pusher
+                    || is_pusher_email(&trailer.value)
+                    || is_pusher_email(&_parse_identity(&trailer.value).email)
+            });
+        if pushed_by_pusher {
+            return Ok(());
+        }
+    }
+
+    Err(ValidationError::CommitterNotPusher {
+        pusher: pusher.to_owned(),
+        email: commit.committer.email.clone(),
+        pushed_by_allowed,
+    })
+}
+
+fn _validator_enforce_squash_merge(commits_range: &[String]) -> Result<(), ValidationError> {
+    if commits_range.len() > 1 {
+        return Err(ValidationError::EnforceSquashMerge {
+            commits: commits_range.len(),
+        });
+    }
+    Ok(())
+}
+
+/// A squashed commit has a single parent (or none, for the first commit of the repository).
+fn _validate_not_merge_commit(commit_parents: &[String]) -> Result<(), ValidationError> {
+    if commit_parents.len() > 1 {
+        return Err(ValidationError::SquashMergeCommit {
+            parents: commit_parents.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Validates the number of parents of a commit against the `linear_history` and `max_parents` rules.
+fn _validate_parents(hook_rules: &UpdateRules, commit_parents: &[String]) -> Vec<ValidationError> {
+    let mut errors = vec![];
+    if let (Some(true), true) = (hook_rules.linear_history, commit_parents.len() > 1) {
+        errors.push(ValidationError::MergeCommitNotAllowed {
+            parents: commit_parents.len(),
+        });
+    }
+    if let Some(max_parents) = hook_rules.max_parents {
+        if commit_parents.len() > max_parents as usize {
+            errors.push(ValidationError::TooManyParents {
+                parents: commit_parents.len(),
+                max_parents,
+            });
+        }
+    }
+    errors
+}
+
+/// Returns an error for every body line that is longer than allowed.
+/// The trailer lines are skipped, since their values (e.g. long "Co-authored-by" identities)
+/// are checked by the trailer rules.
+fn _validate_body_max_line_length(
+    commit_body: &[BodyLine],
+    commit_trailers: &[Trailer],
+    body_max_line_length: u8,
+) -> Vec<ValidationError> {
+    let is_trailer_line = |number: usize| {
+        commit_trailers
+            .iter()
+            .any(|trailer| (trailer.number..trailer.number + trailer.lines).contains(&number))
+    };
+    commit_body
+        .iter()
+        .filter(|line| !is_trailer_line(line.number))
+        .filter_map(|line| {
+            let number_of_characters = line.text.chars().count();
+            if number_of_characters > body_max_line_length as usize {
+                return Some(ValidationError::BodyMaxLineLength {
+                    max_length: body_max_line_length,
+                    length: number_of_characters,
+                    line: line.text.clone(),
+                    line_number: line.number,
+                });
+            }
+            None
+        })
         .collect()
 }
 
-fn _validator_enforce_squash_merge(commits_range: &[String]) -> Result<(), ValidationError> {
-    if commits_range.len() > 1 {
-        return Err(ValidationError::EnforceSquashMerge);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::structs::{FileSizeOverride, RefNameRule};
+
+    /// Creates body lines that start right after the title and the empty line that follows it.
+    fn body_lines(texts: &[&str]) -> Vec<BodyLine> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(index, text)| BodyLine {
+                number: index + 3,
+                text: (*text).to_owned(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_validate_conventional_commit() {
+        let rules = ConventionalCommitsRules {
+            scopes: Some(vec!["api".to_owned(), "ui".to_owned()]),
+            ..Default::default()
+        };
+        assert!(validate_conventional_commit(&rules, "feat: Add the login page", &[]).is_empty());
+        assert!(
+            validate_conventional_commit(&rules, "fix(api)!: Drop the v1 endpoints", &[])
+                .is_empty()
+        );
+        assert_eq!(
+            validate_conventional_commit(&rules, "Add the login page", &[]),
+            vec![ValidationError::ConventionalFormat {
+                title: "Add the login page".to_owned()
+            }]
+        );
+        assert_eq!(
+            validate_conventional_commit(&rules, "feature(db): Add the login page", &[]),
+            vec![
+                ValidationError::ConventionalType {
+                    commit_type: "feature".to_owned(),
+                    types: rules.get_types(),
+                },
+                ValidationError::ConventionalScope {
+                    scope: "db".to_owned(),
+                    scopes: vec!["api".to_owned(), "ui".to_owned()],
+                },
+            ]
+        );
+
+        let rules = ConventionalCommitsRules {
+            types: Some(vec!["feat".to_owned(), "fix".to_owned()]),
+            scope_required: Some(true),
+            breaking_footer_required: Some(true),
+            breaking_marker_required: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(
+            validate_conventional_commit(&rules, "feat!: Drop the v1 endpoints", &[]),
+            vec![
+                ValidationError::ConventionalScopeMissing,
+                ValidationError::ConventionalBreakingFooter,
+            ]
+        );
+        assert_eq!(
+            validate_conventional_commit(
+                &rules,
+                "feat(api): Drop the v1 endpoints",
+                &body_lines(&["BREAKING CHANGE: The v1 endpoints are removed."])
+            ),
+            vec![ValidationError::ConventionalBreakingMarker]
+        );
+        assert!(validate_conventional_commit(
+            &rules,
+            "feat(api)!: Drop the v1 endpoints",
+            &body_lines(&["BREAKING-CHANGE: The v1 endpoints are removed."])
+        )
+        .is_empty());
+
+        // A paragraph in the middle of the body is not a footer.
+        let body = vec![
+            BodyLine {
+                number: 3,
+                text: "BREAKING CHANGE: is what this is not.".to_owned(),
+            },
+            BodyLine {
+                number: 5,
+                text: "Refs: #123".to_owned(),
+            },
+        ];
+        assert_eq!(
+            validate_conventional_commit(&rules, "feat(api)!: Add the v2 endpoints", &body),
+            vec![ValidationError::ConventionalBreakingFooter]
+        );
+        assert!(
+            validate_conventional_commit(&rules, "feat(api): Add the v2 endpoints", &body)
+                .is_empty()
+        );
     }
-    Ok(())
-}
 
-fn _validate_body_max_line_length(
-    commit_bodies: &[Vec<String>],
-    body_max_line_length: u8,
-) -> Result<(), ValidationError> {
-    for commit_body in commit_bodies {
-        for line in commit_body {
-            let number_of_characters = line.chars().count();
-            if number_of_characters > body_max_line_length as usize {
-                return Err(ValidationError::BodyMaxLineLength(body_max_line_length));
-            }
+    #[test]
+    fn test_parse_conventional_title() {
+        for title in [
+            "feat:no space",
+            "feat: ",
+            ": no type",
+            "feat(): empty scope",
+            "feat(api: unclosed scope",
+            "feat(a)(b): two scopes",
+            "new feat: spaces",
+        ] {
+            assert!(_parse_conventional_title(title).is_none(), "{}", title);
         }
+        let title = _parse_conventional_title("fix(ui)!: The title").unwrap();
+        assert_eq!(title.commit_type, "fix");
+        assert_eq!(title.scope, Some("ui"));
+        assert!(title.breaking);
     }
 
-    Ok(())
-}
+    #[test]
+    fn test_extract_ticket_ids() {
+        let regex = create_regex(r"\b[A-Z][A-Z0-9]+-\d+\b").unwrap();
+        assert_eq!(
+            _extract_ticket_ids(&regex, "feature/FOO-123-login FOO-123 BAR2-7 foo-1"),
+            vec!["FOO-123".to_owned(), "BAR2-7".to_owned()]
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let regex = create_regex(r"^(?:feature|bugfix)/(\d+)-").unwrap();
+        assert_eq!(
+            _extract_ticket_ids(&regex, "feature/42-login"),
+            vec!["42".to_owned()]
+        );
+        assert!(_extract_ticket_ids(&regex, "master").is_empty());
+    }
+
+    #[test]
+    fn test_ticket_id_validator() {
+        let ticket_id_rules = TicketIdRules {
+            title_pattern: Some(r"^([A-Z]+-\d+): ".to_owned()),
+            required_in: Some(vec![TicketIdLocation::Title, TicketIdLocation::Branch]),
+            match_branch: Some(true),
+            ..Default::default()
+        };
+
+        let mut validator =
+            TicketIdValidator::new(&ticket_id_rules, "feature/FOO-123-login").unwrap();
+        assert_eq!(validator.validate_branch(), None);
+        assert!(validator
+            .validate_message("FOO-123: Add the login page", &[])
+            .is_empty());
+        assert!(validator
+            .validate_message("FOO-7: Fix the build", &body_lines(&["Part of FOO-123."]))
+            .is_empty());
+        assert_eq!(
+            validator.validate_message("Add the login page for FOO-123", &[]),
+            vec![ValidationError::TicketIdMissing {
+                location: "commit title".to_owned()
+            }]
+        );
+        assert_eq!(
+            validator.validate_message("FOO-7: Fix the build", &[]),
+            vec![ValidationError::TicketIdMismatch {
+                branch: "feature/FOO-123-login".to_owned(),
+                branch_ids: vec!["FOO-123".to_owned()],
+                message_ids: vec!["FOO-7".to_owned()],
+            }]
+        );
+
+        let mut validator = TicketIdValidator::new(&ticket_id_rules, "master").unwrap();
+        assert_eq!(
+            validator.validate_branch(),
+            Some(ValidationError::TicketIdMissing {
+                location: "branch name".to_owned()
+            })
+        );
+        assert!(validator
+            .validate_message("FOO-7: Fix the build", &[])
+            .is_empty());
+    }
 
     #[test]
     fn test_validator_title_format() {
         let regex_string = "^((\\bECSTU\\b)|(\\bINTERSCALE\\b))-\\d{1,}: \\w+.*$".to_owned();
         let regex = regex::Regex::new(&regex_string).unwrap();
 
-        let commit_titles = vec!["ECSTU-123: This is the title description".to_owned()];
-        let result = validate_title_format(&commit_titles, &regex);
+        let result = validate_title_format("ECSTU-123: This is the title description", &regex);
         assert!(result.is_ok());
 
-        let commit_titles = vec!["ECSTU-: This is the title description".to_owned()];
-        let result = validate_title_format(&commit_titles, &regex);
+        let result = validate_title_format("ECSTU-: This is the title description", &regex);
         assert_eq!(
             result.err().unwrap(),
-            ValidationError::TitleFormat(regex_string.clone())
+            ValidationError::TitleFormat {
+                pattern: regex_string.clone(),
+                title: "ECSTU-: This is the title description".to_owned(),
+            }
         );
 
-        let commit_titles = vec!["ECSTU-1:    ".to_owned()];
-        let result = validate_title_format(&commit_titles, &regex);
+        let result = validate_title_format("ECSTU-1:    ", &regex);
         assert_eq!(
             result.err().unwrap(),
-            ValidationError::TitleFormat(regex_string.clone())
+            ValidationError::TitleFormat {
+                pattern: regex_string.clone(),
+                title: "ECSTU-1:    ".to_owned(),
+            }
         );
 
-        let commit_titles = vec!["ECSTU-1: a".to_owned()];
-        let result = validate_title_format(&commit_titles, &regex);
+        let result = validate_title_format("ECSTU-1: a", &regex);
         assert!(result.is_ok());
 
         // New regex
         let regex_string = "^[A-Z]+-\\d{1,}: \\w+.*$".to_owned();
         let regex = regex::Regex::new(&regex_string).unwrap();
 
-        let commit_titles = vec!["HELLO-1: a".to_owned()];
-        let result = validate_title_format(&commit_titles, &regex);
+        let result = validate_title_format("HELLO-1: a", &regex);
         assert!(result.is_ok());
 
-        let commit_titles = vec!["HELLo-1: a".to_owned()];
-        let result = validate_title_format(&commit_titles, &regex);
+        let result = validate_title_format("HELLo-1: a", &regex);
         assert_eq!(
             result.err().unwrap(),
-            ValidationError::TitleFormat(regex_string.clone())
+            ValidationError::TitleFormat {
+                pattern: regex_string.clone(),
+                title: "HELLo-1: a".to_owned(),
+            }
         );
     }
 
     #[test]
     fn test_validator_title_max_length() {
-        let commit_titles = vec![
-            "Title line 1".to_owned(),
-            "Title line 2".to_owned(),
-            "Title line 3".to_owned(),
-        ];
-        let result = _validate_title_max_length(&commit_titles, 12);
+        let result = _validate_title_max_length("Title line 1", 12);
         assert!(result.is_ok());
 
-        let commit_titles = vec![
-            "Bigger title line 1".to_owned(),
-            "Title line 2".to_owned(),
-            "Title line 3".to_owned(),
-        ];
-        let result = _validate_title_max_length(&commit_titles, 12);
-        assert_eq!(result.err().unwrap(), ValidationError::TitleMaxLength(12));
-
-        let commit_titles = vec![
-            "Title line 1".to_owned(),
-            "Title line 2".to_owned(),
-            "Bigger title line 3".to_owned(),
-        ];
-        let result = _validate_title_max_length(&commit_titles, 12);
-        assert_eq!(result.err().unwrap(), ValidationError::TitleMaxLength(12));
+        let result = _validate_title_max_length("Bigger title line 1", 12);
+        assert_eq!(
+            result.err().unwrap(),
+            ValidationError::TitleMaxLength {
+                max_length: 12,
+                length: 19,
+                title: "Bigger title line 1".to_owned(),
+            }
+        );
 
-        let commit_titles = vec![];
-        let result = _validate_title_max_length(&commit_titles, 12);
+        let result = _validate_title_max_length("", 12);
         assert!(result.is_ok())
     }
 
@@ -383,6 +2125,394 @@ This is the body
         );
     }
 
+    #[test]
+    fn test_get_commit_parents() {
+        let commit = "tree d6b3dd4b08f63ba13479484508e0679d32a7891a
+author John Doe <john.doe@gmail.com>
+committer John Doe <john.doe@gmail.com>
+
+This is the commit title";
+        assert!(_get_commit_parents(commit).is_empty());
+
+        let commit = "tree d6b3dd4b08f63ba13479484508e0679d32a7891a
+parent 5c5a43f5c5a43f5c5a43f5c5a43f5c5a43f5c5a4
+parent e418762e418762e418762e418762e418762e4187
+author John Doe <john.doe@gmail.com>
+committer John Doe <john.doe@gmail.com>
+
+This is the commit title
+
+parent this is not a parent";
+        assert_eq!(
+            _get_commit_parents(commit),
+            vec![
+                "5c5a43f5c5a43f5c5a43f5c5a43f5c5a43f5c5a4".to_owned(),
+                "e418762e418762e418762e418762e418762e4187".to_owned()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validator_enforce_squash_merge() {
+        let result = _validator_enforce_squash_merge(&["5c5a43f".to_owned()]);
+        assert!(result.is_ok());
+
+        let result = _validator_enforce_squash_merge(&["5c5a43f".to_owned(), "e418762".to_owned()]);
+        assert_eq!(
+            result.err().unwrap(),
+            ValidationError::EnforceSquashMerge { commits: 2 }
+        );
+
+        let result = _validate_not_merge_commit(&["5c5a43f".to_owned()]);
+        assert!(result.is_ok());
+
+        let result = _validate_not_merge_commit(&["5c5a43f".to_owned(), "e418762".to_owned()]);
+        assert_eq!(
+            result.err().unwrap(),
+            ValidationError::SquashMergeCommit { parents: 2 }
+        );
+    }
+
+    #[test]
+    fn test_get_commit_identity() {
+        let commit = "tree d6b3dd4b08f63ba13479484508e0679d32a7891a
+author John Doe <john.doe@gmail.com> 1690000000 +0300
+committer root <root@localhost> 1690000001 +0000
+
+This is the commit title
+
+committer This is not the committer";
+        assert_eq!(
+            _get_commit_identity(commit, "author"),
+            Identity {
+                name: "John Doe".to_owned(),
+                email: "john.doe@gmail.com".to_owned()
+            }
+        );
+        assert_eq!(
+            _get_commit_identity(commit, "committer"),
+            Identity {
+                name: "root".to_owned(),
+                email: "root@localhost".to_owned()
+            }
+        );
+        assert_eq!(_get_commit_identity(commit, "tagger"), Identity::default());
+    }
+
+    #[test]
+    fn test_identity_validator() {
+        let identity_rules = IdentityRules {
+            allowed_email_domains: Some(vec!["example.com".to_owned(), "*.example.org".to_owned()]),
+            require_full_name: Some(true),
+            deny_local_identities: Some(true),
+            ..Default::default()
+        };
+        let identity_validator = IdentityValidator::new(&identity_rules).unwrap();
+        let identity = |name: &str, email: &str| Identity {
+            name: name.to_owned(),
+            email: email.to_owned(),
+        };
+
+        assert!(identity_validator
+            .validate("author", &identity("John Doe", "john.doe@Example.com"))
+            .is_empty());
+        assert!(identity_validator
+            .validate(
+                "author",
+                &identity("Ludwig van Beethoven", "ludwig@dev.example.org")
+            )
+            .is_empty());
+        assert!(identity_validator
+            .validate("author", &identity("Ludwig van", "ludwig@example.com"))
+            .is_empty());
+        assert!(identity_validator
+            .validate("author", &identity("李 小龙", "bruce.lee@example.com"))
+            .is_empty());
+        assert_eq!(
+            identity_validator.validate("author", &identity("john doe", "john.doe@example.com")),
+            vec![ValidationError::IdentityFullName {
+                role: "author".to_owned(),
+                name: "john doe".to_owned(),
+            }]
+        );
+        assert_eq!(
+            identity_validator.validate("committer", &identity("root", "root@localhost")),
+            vec![
+                ValidationError::IdentityLocal {
+                    role: "committer".to_owned(),
+                    email: "root@localhost".to_owned(),
+                },
+                ValidationError::IdentityEmailDomain {
+                    role: "committer".to_owned(),
+                    email: "root@localhost".to_owned(),
+                    allowed_domains: vec!["example.com".to_owned(), "*.example.org".to_owned()],
+                },
+                ValidationError::IdentityFullName {
+                    role: "committer".to_owned(),
+                    name: "root".to_owned(),
+                },
+            ]
+        );
+
+        let identity_rules = IdentityRules {
+            email_format: Some("^[a-z]+\\.[a-z]+@".to_owned()),
+            name_format: Some("^[A-Z][a-z]+ [A-Z][a-z]+$".to_owned()),
+            ..Default::default()
+        };
+        let identity_validator = IdentityValidator::new(&identity_rules).unwrap();
+        assert!(identity_validator
+            .validate("author", &identity("John Doe", "john.doe@example.com"))
+            .is_empty());
+        assert_eq!(
+            identity_validator
+                .validate("author", &identity("jdoe", "jdoe@example.com"))
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_is_local_email_domain() {
+        assert!(_is_local_email_domain("localhost"));
+        assert!(_is_local_email_domain("laptop.(none)"));
+        assert!(_is_local_email_domain("laptop.localdomain"));
+        assert!(_is_local_email_domain("laptop"));
+        assert!(_is_local_email_domain(""));
+        assert!(!_is_local_email_domain("example.com"));
+    }
+
+    #[test]
+    fn test_get_message_trailers() {
+        let message = "FOO-1: The title
+
+The body. Not: a trailer
+
+Signed-off-by: John Doe <john.doe@example.com>
+Pushed-by : jdoe
+Co-authored-by: Jane Doe
+  <jane.doe@example.com>";
+        assert_eq!(
+            _get_message_trailers(message),
+            vec![
+                Trailer {
+                    key: "Signed-off-by".to_owned(),
+                    value: "John Doe <john.doe@example.com>".to_owned(),
+                    number: 5,
+                    lines: 1,
+                },
+                Trailer {
+                    key: "Pushed-by".to_owned(),
+                    value: "jdoe".to_owned(),
+                    number: 6,
+                    lines: 1,
+                },
+                Trailer {
+                    key: "Co-authored-by".to_owned(),
+                    value: "Jane Doe <jane.doe@example.com>".to_owned(),
+                    number: 7,
+                    lines: 2,
+                },
+            ]
+        );
+
+        // A paragraph with a "Signed-off-by" trailer needs only a quarter of its lines to be trailers.
+        let message = "The title
+
+This is a line.
+(cherry picked from commit 9c6d1ea7d3d8bf4a9e5d2e0d7bfdf3b91f6a5ab1)
+Signed-off-by: John Doe <john.doe@example.com>";
+        assert_eq!(
+            _get_message_trailers(message),
+            vec![Trailer {
+                key: "Signed-off-by".to_owned(),
+                value: "John Doe <john.doe@example.com>".to_owned(),
+                number: 5,
+                lines: 1,
+            }]
+        );
+
+        assert!(_get_message_trailers("Fixes: the title only").is_empty());
+        assert!(_get_message_trailers("The title\n\nPushed-by: jdoe\nNot a trailer").is_empty());
+        assert!(_get_message_trailers("The title\n\nPushed-by: jdoe\n\nThe body").is_empty());
+        assert!(_get_message_trailers("The title\n\nKey with spaces: value").is_empty());
+    }
+
+    #[test]
+    fn test_trailers_validator() {
+        let trailer_rules = vec![
+            TrailerRule {
+                key: "Reviewed-by".to_owned(),
+                required: Some(true),
+                name_email: Some(true),
+                ..Default::default()
+            },
+            TrailerRule {
+                key: "Change-Id".to_owned(),
+                format: Some("^I[0-9a-f]{40}$".to_owned()),
+                ..Default::default()
+            },
+            TrailerRule {
+                key: "WIP".to_owned(),
+                forbidden: Some(true),
+                ..Default::default()
+            },
+        ];
+        let trailers_validator = TrailersValidator::new(&trailer_rules).unwrap();
+        let trailer = |key: &str, value: &str| Trailer {
+            key: key.to_owned(),
+            value: value.to_owned(),
+            ..Default::default()
+        };
+
+        assert!(trailers_validator
+            .validate(&[
+                trailer("reviewed-by", "John Doe <john.doe@example.com>"),
+                trailer("Change-Id", "I8473b95934b5732ac55d26311a706c9c2bde9940"),
+            ])
+            .is_empty());
+        assert_eq!(
+            trailers_validator.validate(&[]),
+            vec![ValidationError::TrailerMissing {
+                key: "Reviewed-by".to_owned()
+            }]
+        );
+        assert_eq!(
+            trailers_validator.validate(&[
+                trailer("Reviewed-by", "John Doe"),
+                trailer("Reviewed-by", "<john.doe@example.com>"),
+                trailer("Change-Id", "1234"),
+                trailer("WIP", "do not merge"),
+            ]),
+            vec![
+                ValidationError::TrailerNameEmail {
+                    key: "Reviewed-by".to_owned(),
+                    value: "John Doe".to_owned()
+                },
+                ValidationError::TrailerNameEmail {
+                    key: "Reviewed-by".to_owned(),
+                    value: "<john.doe@example.com>".to_owned()
+                },
+                ValidationError::TrailerFormat {
+                    key: "Change-Id".to_owned(),
+                    value: "1234".to_owned(),
+                    pattern: "^I[0-9a-f]{40}$".to_owned()
+                },
+                ValidationError::TrailerForbidden {
+                    key: "WIP".to_owned()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_committer_is_pusher() {
+        let mut hook_rules = UpdateRules {
+            committer_must_match_pusher: Some(true),
+            ..Default::default()
+        };
+        let emails = vec!["john.doe@example.com".to_owned()];
+        let mut commit = Commit {
+            committer: Identity {
+                name: "John Doe".to_owned(),
+                email: "John.Doe@example.com".to_owned(),
+            },
+            ..Default::default()
+        };
+        assert!(_validate_committer_is_pusher(&hook_rules, "jdoe", &emails, &commit).is_ok());
+
+        commit.committer.email = "jane.doe@example.com".to_owned();
+        commit.trailers = vec![Trailer {
+            key: "Pushed-by".to_owned(),
+            value: "John Doe <john.doe@example.com>".to_owned(),
+            ..Default::default()
+        }];
+        let error = ValidationError::CommitterNotPusher {
+            pusher: "jdoe".to_owned(),
+            email: "jane.doe@example.com".to_owned(),
+            pushed_by_allowed: false,
+        };
+        assert_eq!(
+            _validate_committer_is_pusher(&hook_rules, "jdoe", &emails, &commit),
+            Err(error)
+        );
+
+        hook_rules.allow_pushed_by_trailer = Some(true);
+        assert!(_validate_committer_is_pusher(&hook_rules, "jdoe", &emails, &commit).is_ok());
+        commit.trailers[0].value = "jdoe".to_owned();
+        assert!(_validate_committer_is_pusher(&hook_rules, "jdoe", &emails, &commit).is_ok());
+        commit.trailers[0].value = "jane".to_owned();
+        assert!(_validate_committer_is_pusher(&hook_rules, "jdoe", &emails, &commit).is_err());
+    }
+
+    #[test]
+    fn test_validate_signed_off_by() {
+        let mut commit = Commit {
+            author: Identity {
+                name: "John Doe".to_owned(),
+                email: "john.doe@example.com".to_owned(),
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            _validate_signed_off_by(&commit),
+            Err(ValidationError::SignedOffByMissing {
+                author: "John Doe <john.doe@example.com>".to_owned()
+            })
+        );
+
+        commit.trailers = vec![Trailer {
+            key: "Signed-off-by".to_owned(),
+            value: "Jane Doe <jane.doe@example.com>".to_owned(),
+            ..Default::default()
+        }];
+        assert_eq!(
+            _validate_signed_off_by(&commit),
+            Err(ValidationError::SignedOffByMismatch {
+                author: "John Doe <john.doe@example.com>".to_owned(),
+                sign_offs: vec!["Jane Doe <jane.doe@example.com>".to_owned()]
+            })
+        );
+
+        commit.trailers.push(Trailer {
+            key: "signed-off-by".to_owned(),
+            value: "john doe <John.Doe@example.com>".to_owned(),
+            ..Default::default()
+        });
+        assert!(_validate_signed_off_by(&commit).is_ok());
+    }
+
+    #[test]
+    fn test_signed_commit() {
+        let commit = "tree d6b3dd4b08f63ba13479484508e0679d32a7891a
+author John Doe <john.doe@example.com> 1690000000 +0300
+committer John Doe <john.doe@example.com> 1690000000 +0300
+gpgsig -----BEGIN PGP SIGNATURE-----
+ 
+ iHUEABYKAB0WIQTsb0c2kZ5MK3Sxs6nHDGL2ZQ0XBQUCZMD2zAAKCRDHDGL2ZQ0X
+ -----END PGP SIGNATURE-----
+
+FOO-1: The title
+
+The body";
+        assert!(_is_commit_signed(commit));
+        assert_eq!(_get_commit_title(commit), "FOO-1: The title");
+        assert_eq!(
+            _get_commit_body(commit),
+            vec![BodyLine {
+                number: 3,
+                text: "The body".to_owned()
+            }]
+        );
+        assert_eq!(_get_commit_identity(commit, "committer").name, "John Doe");
+
+        let commit = "tree d6b3dd4b08f63ba13479484508e0679d32a7891a
+author John Doe <john.doe@example.com> 1690000000 +0300
+
+FOO-1: The title mentions
+gpgsig in the body";
+        assert!(!_is_commit_signed(commit));
+    }
+
     #[test]
     fn test_get_commit_body() {
         let commit = "tree d6b3dd4b08f63ba13479484508e0679d32a7891a
@@ -391,8 +2521,7 @@ committer John Doe <john.doe@gmail.com>
 
 This is the commit title";
         let actual = _get_commit_body(commit);
-        let expected: Vec<String> = vec![];
-        assert_eq!(actual, expected);
+        assert!(actual.is_empty());
 
         let commit = "tree d6b3dd4b08f63ba13479484508e0679d32a7891a
 author John Doe <john.doe@gmail.com>
@@ -402,8 +2531,7 @@ This is the commit title
 
 This is body line 1";
         let actual = _get_commit_body(commit);
-        let expected: Vec<String> = vec!["This is body line 1".to_owned()];
-        assert_eq!(actual, expected);
+        assert_eq!(actual, body_lines(&["This is body line 1"]));
 
         let commit = "tree d6b3dd4b08f63ba13479484508e0679d32a7891a
 author John Doe <john.doe@gmail.com>
@@ -415,8 +2543,7 @@ This is body line 1
 
 ";
         let actual = _get_commit_body(commit);
-        let expected: Vec<String> = vec!["This is body line 1".to_owned()];
-        assert_eq!(actual, expected);
+        assert_eq!(actual, body_lines(&["This is body line 1"]));
 
         let commit = "tree d6b3dd4b08f63ba13479484508e0679d32a7891a
 author John Doe <john.doe@gmail.com>
@@ -432,7 +2559,8 @@ This is body line 5";
         let actual = _get_commit_body(commit);
         let expected_length = 5;
         assert_eq!(actual.len(), expected_length);
-        assert_eq!(actual.last().unwrap(), "This is body line 5");
+        assert_eq!(actual.last().unwrap().text, "This is body line 5");
+        assert_eq!(actual.last().unwrap().number, 7);
 
         let commit = "tree d6b3dd4b08f63ba13479484508e0679d32a7891a
 author John Doe <john.doe@gmail.com>
@@ -450,55 +2578,452 @@ This is body line 5
         let actual = _get_commit_body(commit);
         let expected_length = 5;
         assert_eq!(actual.len(), expected_length);
-        assert_eq!(actual.last().unwrap(), "This is body line 5");
+        assert_eq!(actual.last().unwrap().text, "This is body line 5");
+        assert_eq!(actual.last().unwrap().number, 7);
+    }
+
+    #[test]
+    fn test_get_message_title_and_body() {
+        let message = "This is the commit title";
+        assert_eq!(_get_message_title(message), "This is the commit title");
+        assert!(_get_message_body(message).is_empty());
+
+        let message = "
+This is the commit title
+This line is not part of the body
+
+This is body line 1
+
+This is body line 2
+";
+        assert_eq!(_get_message_title(message), "This is the commit title");
+        assert_eq!(
+            _get_message_body(message),
+            vec![
+                BodyLine {
+                    number: 5,
+                    text: "This is body line 1".to_owned()
+                },
+                BodyLine {
+                    number: 7,
+                    text: "This is body line 2".to_owned()
+                },
+            ]
+        );
+
+        assert_eq!(_get_message_title(""), "");
+        assert!(_get_message_body("").is_empty());
     }
 
     #[test]
     fn test_validator_body_required() {
-        let commit_bodies = vec![
-            vec!["Body line 1".to_owned()],
-            vec!["Body line 1".to_owned()],
-            vec!["Body line 1".to_owned()],
-        ];
-        let result = _validate_body_required(&commit_bodies);
+        let commit_body = body_lines(&["Body line 1"]);
+        let result = _validate_body_required(&commit_body);
         assert!(result.is_ok());
 
-        let commit_bodies = vec![
-            vec!["Body line 1".to_owned()],
-            vec!["Body line 1".to_owned()],
-            vec![],
-        ];
-        let result = _validate_body_required(&commit_bodies);
-        assert_eq!(result.err().unwrap(), ValidationError::BodyRequired);
-
-        let commit_bodies = vec![vec![]];
-        let result = _validate_body_required(&commit_bodies);
+        let commit_body = vec![];
+        let result = _validate_body_required(&commit_body);
         assert_eq!(result.err().unwrap(), ValidationError::BodyRequired);
     }
 
     #[test]
     fn test_validator_body_max_line_length() {
-        let commit_bodies = vec![vec![
-            "Body line 1".to_owned(),
-            "Body line 2".to_owned(),
-            "Body line 3".to_owned(),
-        ]];
-        let result = _validate_body_max_line_length(&commit_bodies, 11);
-        assert!(result.is_ok());
+        let commit_body = body_lines(&["Body line 1", "Body line 2", "Body line 3"]);
+        let result = _validate_body_max_line_length(&commit_body, &[], 11);
+        assert!(result.is_empty());
 
-        let commit_bodies = vec![vec![
-            "Body line 1".to_owned(),
-            "Body line 2".to_owned(),
-            "Bigger body line 3".to_owned(),
-        ]];
-        let result = _validate_body_max_line_length(&commit_bodies, 11);
+        let commit_body = body_lines(&["Bigger body line 1", "Body line 2", "Bigger body line 3"]);
+        let result = _validate_body_max_line_length(&commit_body, &[], 11);
         assert_eq!(
-            result.err().unwrap(),
-            ValidationError::BodyMaxLineLength(11)
+            result,
+            vec![
+                ValidationError::BodyMaxLineLength {
+                    max_length: 11,
+                    length: 18,
+                    line: "Bigger body line 1".to_owned(),
+                    line_number: 3,
+                },
+                ValidationError::BodyMaxLineLength {
+                    max_length: 11,
+                    length: 18,
+                    line: "Bigger body line 3".to_owned(),
+                    line_number: 5,
+                },
+            ]
         );
 
-        let commit_bodies = vec![vec![]];
-        let result = _validate_body_max_line_length(&commit_bodies, 11);
-        assert!(result.is_ok());
+        let commit_body = vec![];
+        let result = _validate_body_max_line_length(&commit_body, &[], 11);
+        assert!(result.is_empty());
+
+        // The trailer lines are checked by the trailer rules instead.
+        let message = "Title\n\nBody line 1\n\nCo-authored-by: Jane Doe\n  <jane.doe@example.com>";
+        let result = _validate_body_max_line_length(
+            &_get_message_body(message),
+            &_get_message_trailers(message),
+            11,
+        );
+        assert!(result.is_empty());
+    }
+
+    #[test]
+    fn test_create_glob_regex() {
+        let regex = create_glob_regex("master").unwrap();
+        assert!(regex.is_match("master"));
+        assert!(!regex.is_match("master2"));
+        assert!(!regex.is_match("old/master"));
+
+        let regex = create_glob_regex("release/*").unwrap();
+        assert!(regex.is_match("release/2.3"));
+        assert!(!regex.is_match("release/2.3/fix"));
+        assert!(!regex.is_match("release"));
+
+        let regex = create_glob_regex("hotfix/**").unwrap();
+        assert!(regex.is_match("hotfix/2.3"));
+        assert!(regex.is_match("hotfix/2.3/fix"));
+
+        let regex = create_glob_regex("**/*.pem").unwrap();
+        assert!(regex.is_match("key.pem"));
+        assert!(regex.is_match("config/keys/key.pem"));
+        assert!(!regex.is_match("key.pem.txt"));
+
+        let regex = create_glob_regex("v?.*").unwrap();
+        assert!(regex.is_match("v1.2"));
+        assert!(!regex.is_match("v10.2"));
+    }
+
+    #[test]
+    fn test_create_path_glob_regex() {
+        let regex = create_path_glob_regex("*.sql").unwrap();
+        assert!(regex.is_match("dump.sql"));
+        assert!(regex.is_match("dumps/2023/dump.sql"));
+        assert!(!regex.is_match("dump.sql.txt"));
+
+        let regex = create_path_glob_regex("/assets/*.png").unwrap();
+        assert!(regex.is_match("assets/logo.png"));
+        assert!(!regex.is_match("assets/icons/logo.png"));
+        assert!(!regex.is_match("web/assets/logo.png"));
+
+        let regex = create_path_glob_regex("dist/").unwrap();
+        assert!(regex.is_match("dist/app.js"));
+        assert!(regex.is_match("web/dist/js/app.js"));
+        assert!(!regex.is_match("dist"));
+        assert!(!regex.is_match("distribution/app.js"));
+    }
+
+    #[test]
+    fn test_forbidden_paths_validator() {
+        let hook_rules: UpdateRules = serde_json::from_str(
+            r#"{ "forbidden_paths": ["*.pem", ".env", { "path": "dist/", "allowed_branches": ["release/*"] }] }"#,
+        )
+        .unwrap();
+        let changed_file = |path: &str, status: char| ChangedFile {
+            path: path.to_owned(),
+            status,
+            old_mode: "100644".to_owned(),
+            new_mode: "100644".to_owned(),
+            old_blob: "".to_owned(),
+            new_blob: "".to_owned(),
+        };
+        let changed_files = vec![
+            changed_file("src/main.rs", 'A'),
+            changed_file("certs/server.pem", 'M'),
+            changed_file("config/.env", 'D'),
+            changed_file("dist/app.js", 'A'),
+        ];
+
+        let validator = ForbiddenPathsValidator::new(&hook_rules, "master")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            validator.validate(&changed_files),
+            vec![
+                ValidationError::ForbiddenPath {
+                    path: "certs/server.pem".to_owned(),
+                    pattern: "*.pem".to_owned(),
+                },
+                ValidationError::ForbiddenPath {
+                    path: "dist/app.js".to_owned(),
+                    pattern: "dist/".to_owned(),
+                },
+            ]
+        );
+
+        let validator = ForbiddenPathsValidator::new(&hook_rules, "release/1.0")
+            .unwrap()
+            .unwrap();
+        assert_eq!(validator.validate(&changed_files).len(), 1);
+        assert!(
+            ForbiddenPathsValidator::new(&UpdateRules::default(), "master")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_validate_parents() {
+        let parents = |count: usize| -> Vec<String> { (0..count).map(|i| i.to_string()).collect() };
+        let hook_rules = UpdateRules {
+            linear_history: Some(true),
+            ..Default::default()
+        };
+        assert!(_validate_parents(&hook_rules, &parents(1)).is_empty());
+        assert_eq!(
+            _validate_parents(&hook_rules, &parents(2)),
+            vec![ValidationError::MergeCommitNotAllowed { parents: 2 }]
+        );
+
+        let hook_rules = UpdateRules {
+            max_parents: Some(2),
+            ..Default::default()
+        };
+        assert!(_validate_parents(&hook_rules, &parents(2)).is_empty());
+        assert_eq!(
+            _validate_parents(&hook_rules, &parents(3)),
+            vec![ValidationError::TooManyParents {
+                parents: 3,
+                max_parents: 2
+            }]
+        );
+        assert!(_validate_parents(&UpdateRules::default(), &parents(3)).is_empty());
+    }
+
+    #[test]
+    fn test_get_signer_email() {
+        assert_eq!(
+            _get_signer_email("John Doe (work) <john.doe@example.com>"),
+            "john.doe@example.com"
+        );
+        assert_eq!(
+            _get_signer_email("john.doe@example.com"),
+            "john.doe@example.com"
+        );
+        assert_eq!(_get_signer_email(""), "");
+    }
+
+    #[test]
+    fn test_parse_lfs_pointer() {
+        let oid = "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393";
+        let pointer = format!(
+            "version https://git-lfs.github.com/spec/v1\noid sha256:{}\nsize 12345\n",
+            oid
+        );
+        assert_eq!(
+            parse_lfs_pointer(pointer.as_bytes()),
+            Ok((oid.to_owned(), 12345))
+        );
+        let pointer = format!(
+            "version https://git-lfs.github.com/spec/v1\next-0-foo sha256:{}\noid sha256:{}\nsize 1\n",
+            oid, oid
+        );
+        assert!(parse_lfs_pointer(pointer.as_bytes()).is_ok());
+
+        assert_eq!(
+            parse_lfs_pointer(b"version https://git-lfs.github.com/spec/v1\nsize 1\n"),
+            Err("the oid is missing".to_owned())
+        );
+        assert_eq!(
+            parse_lfs_pointer(
+                format!(
+                    "version https://git-lfs.github.com/spec/v1\nsize 1\noid sha256:{}\n",
+                    oid
+                )
+                .as_bytes()
+            ),
+            Err("the key \"oid\" is not in order".to_owned())
+        );
+        assert!(parse_lfs_pointer(
+            b"version https://git-lfs.github.com/spec/v1\noid sha256:abc\nsize 1\n"
+        )
+        .is_err());
+        assert!(parse_lfs_pointer(
+            format!(
+                "version https://git-lfs.github.com/spec/v1\noid sha256:{}\nsize -1\n",
+                oid
+            )
+            .as_bytes()
+        )
+        .is_err());
+        assert!(parse_lfs_pointer(
+            format!(
+                "version https://example.com/spec/v2\noid sha256:{}\nsize 1\n",
+                oid
+            )
+            .as_bytes()
+        )
+        .is_err());
+        assert!(parse_lfs_pointer(
+            format!(
+                "version https://git-lfs.github.com/spec/v1\noid sha256:{}\nsize 1",
+                oid
+            )
+            .as_bytes()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_file_size_validator_get_max_file_size() {
+        let hook_rules = UpdateRules {
+            max_file_size: Some(1024),
+            file_size_overrides: Some(vec![
+                FileSizeOverride {
+                    path: "assets/**".to_owned(),
+                    max_file_size: Some(4096),
+                },
+                FileSizeOverride {
+                    path: "*.bin".to_owned(),
+                    max_file_size: None,
+                },
+            ]),
+            ..Default::default()
+        };
+        let validator = FileSizeValidator::new(&hook_rules).unwrap().unwrap();
+        assert_eq!(validator.get_max_file_size("src/main.rs"), Some(1024));
+        assert_eq!(
+            validator.get_max_file_size("assets/images/logo.png"),
+            Some(4096)
+        );
+        assert_eq!(validator.get_max_file_size("firmware/image.bin"), None);
+        assert!(FileSizeValidator::new(&UpdateRules::default())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_get_branch_rules() {
+        let rule_sets = vec![
+            UpdateRules {
+                branches: Some(vec!["main".to_owned()]),
+                title_max_length: Some(1),
+                ..Default::default()
+            },
+            UpdateRules {
+                branches: Some(vec![
+                    "feature/*".to_owned(),
+                    "regex:^bugfix/[A-Z]+-\\d+$".to_owned(),
+                ]),
+                title_max_length: Some(2),
+                ..Default::default()
+            },
+            UpdateRules {
+                branches: Some(vec!["feature/**".to_owned()]),
+                title_max_length: Some(3),
+                ..Default::default()
+            },
+        ];
+        let title_max_length = |branch: &str| {
+            get_branch_rules(&rule_sets, branch)
+                .unwrap()
+                .and_then(|rules| rules.title_max_length)
+        };
+        assert_eq!(title_max_length("main"), Some(1));
+        assert_eq!(title_max_length("feature/foo"), Some(2));
+        assert_eq!(title_max_length("bugfix/FOO-1"), Some(2));
+        assert_eq!(title_max_length("feature/foo/bar"), Some(3));
+        assert_eq!(title_max_length("bugfix/foo"), None);
+
+        let rule_sets = vec![UpdateRules {
+            branches: Some(vec!["regex:(".to_owned()]),
+            ..Default::default()
+        }];
+        assert_eq!(
+            get_branch_rules(&rule_sets, "main").err().unwrap(),
+            ValidationError::RegexCompilation("(".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_validate_ref_name() {
+        let ref_name_rules = RefNameRules {
+            branches: Some(RefNameRule {
+                format: "^(feature|bugfix|hotfix)/[A-Z]+-\\d+-.+$".to_owned(),
+                description: Some("Use <type>/<TICKET>-<description>".to_owned()),
+            }),
+            tags: Some(RefNameRule {
+                format: "^v?\\d+\\.\\d+\\.\\d+$".to_owned(),
+                description: None,
+            }),
+            severity: None,
+        };
+        assert_eq!(
+            _validate_ref_name(&ref_name_rules, "feature/FOO-123-login").unwrap(),
+            None
+        );
+        assert_eq!(
+            _validate_ref_name(&ref_name_rules, "asdf").unwrap(),
+            Some(ValidationError::RefNameFormat {
+                ref_type: "branch".to_owned(),
+                ref_name: "asdf".to_owned(),
+                pattern: "^(feature|bugfix|hotfix)/[A-Z]+-\\d+-.+$".to_owned(),
+                description: Some("Use <type>/<TICKET>-<description>".to_owned()),
+            })
+        );
+        assert_eq!(
+            _validate_ref_name(&ref_name_rules, "refs/tags/v1.2.3").unwrap(),
+            None
+        );
+        assert_eq!(
+            _validate_ref_name(&ref_name_rules, "refs/tags/test").unwrap(),
+            Some(ValidationError::RefNameFormat {
+                ref_type: "tag".to_owned(),
+                ref_name: "test".to_owned(),
+                pattern: "^v?\\d+\\.\\d+\\.\\d+$".to_owned(),
+                description: None,
+            })
+        );
+        assert_eq!(
+            _validate_ref_name(&ref_name_rules, "refs/notes/commits").unwrap(),
+            None
+        );
+
+        let ref_name_rules = RefNameRules::default();
+        assert_eq!(_validate_ref_name(&ref_name_rules, "asdf").unwrap(), None);
+    }
+
+    #[test]
+    fn test_validate_message_reports_all_failures() {
+        let hook_rules = UpdateRules {
+            title_max_length: Some(10),
+            title_format: Some("^FOO-\\d+: .+$".to_owned()),
+            body_required: Some(true),
+            body_max_line_length: Some(72),
+            ..Default::default()
+        };
+        let regex = _create_title_regex(&hook_rules).unwrap();
+
+        let errors = _validate_message(
+            &hook_rules,
+            regex.as_ref(),
+            None,
+            "A title that is too long",
+            &[],
+            &[],
+        );
+        assert_eq!(
+            errors,
+            vec![
+                ValidationError::TitleFormat {
+                    pattern: "^FOO-\\d+: .+$".to_owned(),
+                    title: "A title that is too long".to_owned(),
+                },
+                ValidationError::TitleMaxLength {
+                    max_length: 10,
+                    length: 24,
+                    title: "A title that is too long".to_owned(),
+                },
+                ValidationError::BodyRequired,
+            ]
+        );
+
+        let errors = _validate_message(
+            &hook_rules,
+            regex.as_ref(),
+            None,
+            "FOO-1: a",
+            &body_lines(&["Body"]),
+            &[],
+        );
+        assert!(errors.is_empty());
     }
 }
@@ -0,0 +1,172 @@
+//! Helpers for the tests that push to a bare repository, which has the hooks installed,
+//! or that commit in a repository with the client side hooks.
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+pub const RULES: &str = r#"{
+  "update": {
+    "title_max_length": 50,
+    "title_format": "^FOO-\\d+: .+$"
+  }
+}"#;
+
+pub struct TestRepos {
+    pub root: PathBuf,
+}
+
+impl TestRepos {
+    /// Creates a bare "remote.git" repository with the hook installed,
+    /// and a "work" repository that pushes to it. The client side "commit-msg" hook is installed in the "work" repository.
+    pub fn new(name: &str, hook: &str, rules: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("gpe-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("work")).unwrap();
+        let repos = Self { root };
+
+        let rules_file = repos.root.join("rules.json");
+        std::fs::write(&rules_file, rules).unwrap();
+
+        repos.git_in(&repos.root, &["init", "--quiet", "--bare", "remote.git"]);
+        repos.git(&["init", "--quiet"]);
+        repos.git(&["symbolic-ref", "HEAD", "refs/heads/master"]);
+        repos.git(&["remote", "add", "origin", "../remote.git"]);
+        repos.install_hook(hook, &rules_file);
+        repos
+    }
+
+    /// Installs the hook script of the repository, pointing it to the test executable and rules.
+    fn install_hook(&self, hook: &str, rules_file: &Path) {
+        let hooks_directory = match hook {
+            "commit-msg" => self.root.join("work").join(".git").join("hooks"),
+            _ => self.root.join("remote.git").join("hooks"),
+        };
+        let script = std::fs::read_to_string(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("hooks")
+                .join(hook),
+        )
+        .unwrap();
+        let script: Vec<String> = script
+            .lines()
+            .map(|line| {
+                if line.starts_with("executable=") {
+                    format!("executable=\"{}\"", env!("CARGO_BIN_EXE_gpe"))
+                } else if line.starts_with("rules_file=") {
+                    format!("rules_file=\"{}\"", rules_file.display())
+                } else {
+                    line.to_owned()
+                }
+            })
+            .collect();
+        std::fs::create_dir_all(&hooks_directory).unwrap();
+        let hook_path = hooks_directory.join(hook);
+        std::fs::write(&hook_path, script.join("\n")).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+    }
+
+    /// Writes a file in the root directory, e.g. a file that the rules refer to, and returns its path.
+    pub fn write_file(&self, name: &str, contents: &str) -> PathBuf {
+        let path = self.root.join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    pub fn git_in(&self, directory: &Path, args: &[&str]) -> Output {
+        self.git_with_env(directory, args, &[])
+    }
+
+    /// Runs git with extra environment variables, which the hooks inherit too.
+    /// They override the default identity of the commits.
+    pub fn git_with_env(&self, directory: &Path, args: &[&str], env: &[(&str, &str)]) -> Output {
+        Command::new("git")
+            .args(args)
+            .current_dir(directory)
+            .env("GIT_CONFIG_NOSYSTEM", "1")
+            .env("GIT_CONFIG_GLOBAL", "/dev/null")
+            .env("GIT_AUTHOR_NAME", "John Doe")
+            .env("GIT_AUTHOR_EMAIL", "john.doe@example.com")
+            .env("GIT_COMMITTER_NAME", "John Doe")
+            .env("GIT_COMMITTER_EMAIL", "john.doe@example.com")
+            .envs(env.iter().copied())
+            .output()
+            .unwrap()
+    }
+
+    /// Runs git in the "work" repository.
+    pub fn git(&self, args: &[&str]) -> Output {
+        self.git_in(&self.root.join("work"), args)
+    }
+
+    /// Creates an empty commit in the "work" repository and returns its hash.
+    pub fn commit(&self, message: &str) -> String {
+        let output = self.git(&["commit", "--quiet", "--allow-empty", "-m", message]);
+        assert!(output.status.success(), "{:?}", output);
+        self.rev_parse("HEAD")
+    }
+
+    /// Creates an empty commit in the "work" repository, and returns if the commit succeeded along with its output.
+    /// Unlike `commit`, the commit may be aborted by the "commit-msg" hook.
+    pub fn try_commit(&self, message: &str) -> (bool, String) {
+        let output = self.git(&["commit", "--quiet", "--allow-empty", "-m", message]);
+        (
+            output.status.success(),
+            format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        )
+    }
+
+    /// Writes a file in the "work" repository and commits it, and returns the commit hash.
+    pub fn commit_file(&self, path: &str, contents: &[u8], message: &str) -> String {
+        let file_path = self.root.join("work").join(path);
+        std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+        std::fs::write(&file_path, contents).unwrap();
+        self.git(&["add", path]);
+        self.commit(message)
+    }
+
+    /// Merges a branch into the checked out one, with a file that the merge commit adds itself, and returns its hash.
+    pub fn merge_file(&self, branch: &str, path: &str, contents: &[u8], message: &str) -> String {
+        let output = self.git(&["merge", "--quiet", "--no-ff", "--no-commit", branch]);
+        assert!(output.status.success(), "{:?}", output);
+        self.commit_file(path, contents, message)
+    }
+
+    pub fn rev_parse(&self, revision: &str) -> String {
+        let output = self.git(&["rev-parse", revision]);
+        String::from_utf8(output.stdout).unwrap().trim().to_owned()
+    }
+
+    /// Pushes from the "work" repository, and returns if the push succeeded along with its output.
+    pub fn push(&self, args: &[&str]) -> (bool, String) {
+        self.push_as("", args)
+    }
+
+    /// Pushes as a GitLab user, which the hooks get through the "GL_USERNAME" variable.
+    pub fn push_as(&self, user: &str, args: &[&str]) -> (bool, String) {
+        let mut push_args = vec!["push", "origin"];
+        push_args.extend(args);
+        let output = self.git_with_env(
+            &self.root.join("work"),
+            &push_args,
+            &[("GL_USERNAME", user)],
+        );
+        (
+            output.status.success(),
+            String::from_utf8_lossy(&output.stderr).into_owned(),
+        )
+    }
+}
+
+impl Drop for TestRepos {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}